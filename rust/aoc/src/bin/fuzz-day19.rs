@@ -0,0 +1,32 @@
+// runs generated day 19 inputs through the parser, validator and every
+// evaluator until one fails or panics (forever without --iterations); a
+// failure names the seed that reproduces it
+use std::{env, process};
+
+const USAGE: &str = "usage: fuzz-day19 [--seed=N] [--iterations=N]";
+
+fn main() {
+    let mut seed = 0;
+    let mut iterations = 0;
+    for arg in env::args().skip(1) {
+        let parsed = match arg.split_once('=') {
+            Some(("--seed", value)) => value.parse().map(|value| seed = value),
+            Some(("--iterations", value)) => value.parse().map(|value| iterations = value),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(1);
+            }
+        };
+        if parsed.is_err() {
+            eprintln!("invalid value in {}", arg);
+            process::exit(1);
+        }
+    }
+    match aoc::day19::fuzz(seed, iterations) {
+        Ok(res) => println!("{}", res),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::{io::BufRead, str::FromStr};
+
+use crate::input;
+
+use dictionary::Dictionary;
+use matcher::Matcher;
+
+mod dictionary;
+mod matcher;
+
+// part 1 only counts numerals, part 2 spelled digits as well
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Digits,
+    DigitsAndWords,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(part: &str) -> Result<Self, Self::Err> {
+        match part {
+            "1" => Ok(Mode::Digits),
+            "2" => Ok(Mode::DigitsAndWords),
+            _ => Err(format!("unknown part {}, expected 1 or 2", part)),
+        }
+    }
+}
+
+// `dictionary` is a preset name or a dictionary file, english by default;
+// `path` can be `-` for stdin
+pub fn solve(path: &str, mode: Mode, dictionary: Option<&str>) -> Result<String, String> {
+    let words = match (mode, dictionary) {
+        (Mode::Digits, None) => Dictionary::empty(),
+        (Mode::Digits, Some(_)) => return Err("part 1 doesn't use a dictionary".to_string()),
+        (Mode::DigitsAndWords, dictionary) => Dictionary::load(dictionary.unwrap_or("en"))?,
+    };
+    calibrate(input::open(path)?, &words)
+        .map(|val| val.to_string())
+        .map_err(|err| format!("{}: {}", input::name(path), err))
+}
+
+// u64 since a big enough input of 99s overflows a u32
+fn calibrate(reader: impl BufRead, words: &Dictionary) -> Result<u64, String> {
+    let matcher = Matcher::new(words);
+    input::lines(reader).try_fold(0, |acc, line| {
+        let (number, line) = line?;
+        let val =
+            handle_line(&line, &matcher).map_err(|err| format!("line {}: {}", number, err))?;
+        Ok(acc + val as u64)
+    })
+}
+
+// a line with a first digit always has a last one, even if it's the same one
+fn handle_line(s: &str, matcher: &Matcher) -> Result<u32, String> {
+    let (first, last) = matcher
+        .first_and_last(s)
+        .ok_or(format!("no digits in `{}`", s))?;
+    Ok(first * 10 + last)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    // the digit written at the very start of `s`, as a numeral or a word
+    fn digit_at(s: &str, words: &Dictionary) -> Option<u32> {
+        let first = s.chars().next()?;
+        if let Some(digit) = first.to_digit(10) {
+            return Some(digit);
+        }
+        words
+            .words()
+            .iter()
+            .find(|(word, _)| s.starts_with(word.as_str()))
+            .map(|(_, digit)| *digit)
+    }
+
+    // spelled digits can share letters (`eightwo` is 8 then 2), so rather than
+    // replacing words left to right, look for a digit starting at every position:
+    // forward for the first one and backward for the last; this tries every word
+    // at every position, so it's only the reference the matcher is tested against
+    fn first_digit(s: &str, words: &Dictionary) -> Option<u32> {
+        s.char_indices().find_map(|(i, _)| digit_at(&s[i..], words))
+    }
+
+    fn last_digit(s: &str, words: &Dictionary) -> Option<u32> {
+        s.char_indices()
+            .rev()
+            .find_map(|(i, _)| digit_at(&s[i..], words))
+    }
+
+    fn value(line: &str, words: &Dictionary) -> Option<u32> {
+        handle_line(line, &Matcher::new(words)).ok()
+    }
+
+    #[test]
+    fn puzzle_examples() {
+        let none = Dictionary::empty();
+        let en = Dictionary::preset("en").unwrap();
+        assert_eq!(value("1abc2", &none), Some(12));
+        assert_eq!(value("pqr3stu8vwx", &none), Some(38));
+        assert_eq!(value("a1b2c3d4e5f", &none), Some(15));
+        assert_eq!(value("treb7uchet", &none), Some(77));
+        assert_eq!(value("two1nine", &en), Some(29));
+        assert_eq!(value("eightwothree", &en), Some(83));
+        assert_eq!(value("abcone2threexyz", &en), Some(13));
+        assert_eq!(value("xtwone3four", &en), Some(24));
+        assert_eq!(value("4nineeightseven2", &en), Some(42));
+        assert_eq!(value("zoneight234", &en), Some(14));
+        assert_eq!(value("7pqrstsixteen", &en), Some(76));
+        assert_eq!(value("treb7uchet", &en), Some(77));
+    }
+
+    #[test]
+    fn part_1_ignores_words() {
+        let none = Dictionary::empty();
+        assert_eq!(value("two1nine", &none), Some(11));
+        assert_eq!(value("eightwothree", &none), None);
+    }
+
+    #[test]
+    fn lines_without_digits() {
+        let en = Dictionary::preset("en").unwrap();
+        assert_eq!(value("", &en), None);
+        assert_eq!(value("abcdef", &en), None);
+        assert_eq!(value("xunozwei", &en), None);
+    }
+
+    #[test]
+    fn other_languages() {
+        let preset = |name| Dictionary::preset(name).unwrap();
+        assert_eq!(value("xuno7zwei", &preset("es")), Some(17));
+        assert_eq!(value("xuno7zwei", &preset("de")), Some(72));
+        assert_eq!(value("fünfunddrei", &preset("de")), Some(53));
+        assert_eq!(value("trois4", &preset("fr")), Some(34));
+        let zero = Dictionary::parse("# zero counts too\nzero 0\none 1\ntwo 2\n").unwrap();
+        assert_eq!(value("zerone", &zero), Some(1));
+        assert_eq!(value("twozero", &zero), Some(20));
+    }
+
+    // every pair of spelled digits in each preset that can share letters, which
+    // run together must still give both digits
+    #[test]
+    fn overlapping_words() {
+        for words in Dictionary::presets() {
+            for (first, first_digit) in words.words() {
+                for (last, last_digit) in words.words() {
+                    for (shared, _) in last.char_indices().skip(1) {
+                        if first.len() > shared && first.ends_with(&last[..shared]) {
+                            let line = format!("{}{}", first, &last[shared..]);
+                            assert_eq!(
+                                value(&line, &words),
+                                Some(first_digit * 10 + last_digit),
+                                "{}",
+                                line
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn long_line() {
+        let line = format!("{}{}", "éeightwo".repeat(200_000), "x");
+        assert_eq!(value(&line, &Dictionary::preset("en").unwrap()), Some(82));
+    }
+
+    // random lines made of bits of words, numerals and multibyte chars, where
+    // the matcher must find the same digits as the scan
+    #[test]
+    fn matcher_agrees_with_scan() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // words hiding inside or at the end of other words, which no preset has
+        let nested = Dictionary::parse("un 1\nune 1\nbrun 2\nneun 9\nrune 4\nzero 0").unwrap();
+        for words in Dictionary::presets().chain([nested]) {
+            let matcher = Matcher::new(&words);
+            let mut pieces: Vec<String> = vec![
+                "7".to_string(),
+                "é".to_string(),
+                "ü".to_string(),
+                "€".to_string(),
+            ];
+            for (word, _) in words.words() {
+                for (i, _) in word.char_indices() {
+                    pieces.push(word[i..].to_string());
+                    pieces.push(word[..i].to_string());
+                }
+            }
+            for _ in 0..1000 {
+                let line: String = (0..rng.random_range(0..8))
+                    .map(|_| pieces[rng.random_range(0..pieces.len())].as_str())
+                    .collect();
+                let scanned = first_digit(&line, &words).zip(last_digit(&line, &words));
+                assert_eq!(matcher.first_and_last(&line), scanned, "`{}`", line);
+            }
+        }
+    }
+
+    #[test]
+    fn calibrates_a_reader() {
+        let none = Dictionary::empty();
+        let en = Dictionary::preset("en").unwrap();
+        let input = "1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet\n";
+        assert_eq!(calibrate(input.as_bytes(), &none), Ok(142));
+        let input = "two1nine\neightwothree\nabcone2threexyz\nxtwone3four
+4nineeightseven2\nzoneight234\n7pqrstsixteen\n";
+        assert_eq!(calibrate(input.as_bytes(), &en), Ok(281));
+        assert_eq!(
+            calibrate("1abc2\n\nabc".as_bytes(), &none),
+            Err("line 2: no digits in ``".to_string())
+        );
+        let input: &[u8] = b"1abc2\n\xff1\n";
+        assert_eq!(
+            calibrate(input, &none),
+            Err("line 2: stream did not contain valid UTF-8".to_string())
+        );
+    }
+
+    #[test]
+    fn rejected_dictionaries() {
+        for input in [
+            "un 1\nuno 2",
+            "sept 7\nseptante 70",
+            "1one 1",
+            "one",
+            "one 1 2",
+        ] {
+            assert!(Dictionary::parse(input).is_err(), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::fs;
+
+// the spelled digits part 2 looks for, in the puzzle's english by default
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Dictionary {
+    words: Vec<(String, u32)>,
+}
+
+const PRESETS: [(&str, [&str; 9]); 5] = [
+    (
+        "en",
+        [
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ],
+    ),
+    (
+        "de",
+        [
+            "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+        ],
+    ),
+    (
+        "fr",
+        [
+            "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+        ],
+    ),
+    (
+        "es",
+        [
+            "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve",
+        ],
+    ),
+    (
+        "it",
+        [
+            "uno", "due", "tre", "quattro", "cinque", "sei", "sette", "otto", "nove",
+        ],
+    ),
+];
+
+impl Dictionary {
+    // part 1: no words at all, only numerals count
+    pub(super) fn empty() -> Self {
+        Dictionary { words: Vec::new() }
+    }
+
+    // one to nine in one of the built-in languages
+    pub(super) fn preset(name: &str) -> Option<Self> {
+        let (_, words) = PRESETS.iter().find(|(preset, _)| *preset == name)?;
+        let words = words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| (word.to_string(), i as u32 + 1))
+            .collect();
+        Some(Dictionary { words })
+    }
+
+    // a preset name, or else the path of a dictionary file
+    pub(super) fn load(name: &str) -> Result<Self, String> {
+        if let Some(dictionary) = Dictionary::preset(name) {
+            return Ok(dictionary);
+        }
+        let input = fs::read_to_string(name).map_err(|err| {
+            format!(
+                "{} is neither a preset ({}) nor a readable file: {}",
+                name,
+                PRESETS.map(|(preset, _)| preset).join(", "),
+                err
+            )
+        })?;
+        Dictionary::parse(&input).map_err(|err| format!("{}: {}", name, err))
+    }
+
+    // one `word digit` pair per line, e.g. `zero 0` or `trois 3`; blank lines
+    // and `#` comments are skipped
+    pub(super) fn parse(input: &str) -> Result<Self, String> {
+        let mut words = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (word, digit) = match line.split_whitespace().collect::<Vec<&str>>()[..] {
+                [word, digit] => (word, digit),
+                _ => {
+                    return Err(format!(
+                        "line {}: expected `word digit`, got `{}`",
+                        i + 1,
+                        line
+                    ))
+                }
+            };
+            let digit = digit
+                .parse()
+                .ok()
+                .filter(|digit| *digit <= 9)
+                .ok_or(format!("line {}: {} is not a digit", i + 1, digit))?;
+            words.push((word.to_string(), digit));
+        }
+        Dictionary::new(words)
+    }
+
+    // the scan tries every word at each position and takes whichever matches,
+    // so a word may not start with a numeral, and where one word starts
+    // another (`sept` and `septante`) they have to mean the same digit
+    pub(super) fn new(words: Vec<(String, u32)>) -> Result<Self, String> {
+        for (word, digit) in &words {
+            if word.is_empty() {
+                return Err("empty word".to_string());
+            }
+            if word.starts_with(|first: char| first.is_ascii_digit()) {
+                return Err(format!("`{}` can't be told apart from a numeral", word));
+            }
+            if *digit > 9 {
+                return Err(format!("{} is not a digit", digit));
+            }
+        }
+        for (i, (word, digit)) in words.iter().enumerate() {
+            for (other, other_digit) in &words[i + 1..] {
+                let (shorter, longer) = if word.len() <= other.len() {
+                    (word, other)
+                } else {
+                    (other, word)
+                };
+                if longer.starts_with(shorter.as_str()) && digit != other_digit {
+                    return Err(format!(
+                        "`{}` ({}) and `{}` ({}) are ambiguous",
+                        word, digit, other, other_digit
+                    ));
+                }
+            }
+        }
+        Ok(Dictionary { words })
+    }
+
+    pub(super) fn words(&self) -> &[(String, u32)] {
+        &self.words
+    }
+
+    #[cfg(test)]
+    pub(super) fn presets() -> impl Iterator<Item = Dictionary> {
+        PRESETS
+            .iter()
+            .map(|(name, _)| Dictionary::preset(name).unwrap())
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+use super::dictionary::Dictionary;
+
+// a match ending at some position: how many bytes it spans and its digit
+type Found = Option<(usize, u32)>;
+
+struct State {
+    next: [usize; 256],
+    // of all the patterns ending here (including through the failure links),
+    // the longest starts earliest and the shortest starts latest; those are
+    // the only two that can be a line's first or last digit
+    longest: Found,
+    shortest: Found,
+}
+
+impl State {
+    fn new() -> Self {
+        State {
+            next: [0; 256],
+            longest: None,
+            shortest: None,
+        }
+    }
+}
+
+// aho-corasick over the bytes of the numerals and every dictionary word,
+// built once and then run over each line in a single pass; patterns and
+// lines are both utf-8, so a pattern can only ever match on char boundaries
+pub(super) struct Matcher {
+    states: Vec<State>,
+}
+
+fn longer(a: Found, b: Found) -> Found {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b.0 > a.0 { b } else { a }),
+        _ => a.or(b),
+    }
+}
+
+fn shorter(a: Found, b: Found) -> Found {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b.0 < a.0 { b } else { a }),
+        _ => a.or(b),
+    }
+}
+
+impl Matcher {
+    pub(super) fn new(words: &Dictionary) -> Self {
+        let numerals: Vec<(String, u32)> =
+            (0..10).map(|digit| (digit.to_string(), digit)).collect();
+        // the trie, with 0 as "no edge yet" since nothing points back at the root
+        let mut states = vec![State::new()];
+        for (word, digit) in numerals.iter().chain(words.words()) {
+            let mut state = 0;
+            for byte in word.bytes() {
+                if states[state].next[byte as usize] == 0 {
+                    states.push(State::new());
+                    states[state].next[byte as usize] = states.len() - 1;
+                }
+                state = states[state].next[byte as usize];
+            }
+            let found = Some((word.len(), *digit));
+            states[state].longest = found;
+            states[state].shortest = found;
+        }
+        // breadth first, so a state's failure target is finished before it;
+        // missing edges are filled in from the failure target, which turns
+        // the trie into a full automaton
+        let mut fail = vec![0; states.len()];
+        let mut queue: VecDeque<usize> = states[0]
+            .next
+            .iter()
+            .copied()
+            .filter(|child| *child != 0)
+            .collect();
+        while let Some(state) = queue.pop_front() {
+            let back = fail[state];
+            states[state].longest = longer(states[state].longest, states[back].longest);
+            states[state].shortest = shorter(states[state].shortest, states[back].shortest);
+            for byte in 0..256 {
+                let child = states[state].next[byte];
+                if child == 0 {
+                    states[state].next[byte] = states[back].next[byte];
+                } else {
+                    fail[child] = states[back].next[byte];
+                    queue.push_back(child);
+                }
+            }
+        }
+        Matcher { states }
+    }
+
+    // the first and last digit of the line, which may share letters
+    pub(super) fn first_and_last(&self, line: &str) -> Option<(u32, u32)> {
+        let mut first: Option<(usize, u32)> = None;
+        let mut last: Option<(usize, u32)> = None;
+        let mut state = 0;
+        for (end, byte) in line.bytes().enumerate() {
+            state = self.states[state].next[byte as usize];
+            let State {
+                longest, shortest, ..
+            } = &self.states[state];
+            if let Some((len, digit)) = longest {
+                let start = end + 1 - len;
+                if first.is_none_or(|(first, _)| start < first) {
+                    first = Some((start, *digit));
+                }
+            }
+            if let Some((len, digit)) = shortest {
+                let start = end + 1 - len;
+                if last.is_none_or(|(last, _)| start > last) {
+                    last = Some((start, *digit));
+                }
+            }
+        }
+        Some((first?.1, last?.1))
+    }
+}
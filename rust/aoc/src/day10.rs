@@ -18,20 +18,20 @@ fn create_node(graph: &mut Graph) -> NodeId {
   let node = Node { id, edges: Vec::new() };
   graph.nodes.insert(id, node);
   graph.next_id += 1;
-  return id;
+  id
 }
 
 fn get_node(graph: &Graph, id: NodeId) -> &Node {
-  return graph.nodes.get(&id).expect("Node not found");
+  graph.nodes.get(&id).expect("Node not found")
 }
 
-fn add_edge(graph: &mut Graph, id1: NodeId, id2: NodeId) -> () {
+fn add_edge(graph: &mut Graph, id1: NodeId, id2: NodeId) {
   graph.nodes.get_mut(&id1).expect("Node not found").edges.push(id2);
   graph.nodes.get_mut(&id2).expect("Node not found").edges.push(id1);
 }
 
 fn get_root(graph: &Graph) -> &Node {
-  return get_node(graph, 0)
+  get_node(graph, 0)
 }
 
 fn find_furthest(graph: &Graph) -> u32 {
@@ -39,8 +39,7 @@ fn find_furthest(graph: &Graph) -> u32 {
   let mut distances: HashMap<u32, u32> = HashMap::new();
   let mut queue = root.edges.clone();
   distances.insert(root.id, 0);
-  while !queue.is_empty() {
-    let node_id = queue.pop().expect("Queue is empty");
+  while let Some(node_id) = queue.pop() {
     let node = get_node(graph, node_id);
     for new_node_id in node.edges.clone() {
       if !distances.contains_key(&new_node_id) {
@@ -50,7 +49,7 @@ fn find_furthest(graph: &Graph) -> u32 {
       }
     }
   }
-  distances.values().max().unwrap_or(&0).clone()
+  *distances.values().max().unwrap_or(&0)
   // return max_node;
 }
 
@@ -72,5 +71,5 @@ fn day_ten() -> u32 {
   add_edge(&mut graph, two, four);
   add_edge(&mut graph, four, six);
   add_edge(&mut graph, six, seven);
-  return find_furthest(&graph)
+  find_furthest(&graph)
 }
\ No newline at end of file
@@ -1,99 +1,122 @@
-use core::num;
-use std::fs;
+use rand::{rngs::StdRng, SeedableRng};
+use std::io::BufRead;
+
+use crate::input;
+
+mod forced;
+mod nonogram;
+mod sample;
 
 struct Config {
   row: String,
   groups: Vec<usize>
 }
 
-pub(crate) fn main() -> () {
-  let input = fs::read_to_string("/Users/deverkemmenash/Desktop/2023/AoC/rust/aoc/inputs/day_12.txt").unwrap();
-  let res: usize = input.lines().map(|row| count_valid(row_to_config(row.to_string()))).sum();
-  println!("res: {}", res)
+// one row at a time, so the input can be bigger than memory; `path` can be
+// `-` for stdin. every bad line is reported rather than just the first
+pub fn solve(path: &str) -> Result<String, String> {
+  let name = input::name(path);
+  match total_arrangements(input::open(path)?) {
+    Ok(res) => Ok(res.to_string()),
+    Err(errors) => Err(errors.iter().map(|err| format!("{}: {}", name, err)).collect::<Vec<String>>().join("\n")),
+  }
 }
 
-fn row_to_config(row: String) -> Config {
-  let mut iter = row.split(" ");
-  let template = iter.next().expect("empty row");
-  let groups = iter.next().expect("incomplete row");
-  Config {
-    row: template.to_string(),
-    groups: groups.split(",").map(|c| c.parse::<usize>().expect("invalid groups")).collect()
+// the arrangements of every record added up, or a `line N: reason` for each
+// bad one; a line that can't be read at all ends the input there
+fn total_arrangements(reader: impl BufRead) -> Result<u128, Vec<String>> {
+  let mut res: u128 = 0;
+  let mut errors = Vec::new();
+  for line in input::lines(reader) {
+    let (number, row) = match line {
+      Ok(line) => line,
+      Err(err) => {
+        errors.push(err);
+        break;
+      }
+    };
+    if row.trim().is_empty() {
+      continue;
+    }
+    let total = row_to_config(&row).and_then(count_valid).and_then(|count| {
+      res.checked_add(count).ok_or("the total number of arrangements doesn't fit in a u128".to_string())
+    });
+    match total {
+      Ok(total) => res = total,
+      Err(err) => errors.push(format!("line {}: {}", number, err)),
+    }
+  }
+  if !errors.is_empty() {
+    return Err(errors);
   }
+  Ok(res)
 }
 
-fn count_valid(config: Config) -> usize {
-  let options = gen_row(&config);
-  let valid: Vec<&String> = options.iter().filter(|cfg| check(cfg.to_string(), &config.groups)).collect();
-  let num_valid = valid.iter().count();
-  // println!("# Valid: {}", num_valid);
-  num_valid
+// a record like `???.### 1,1,3`; a row that contradicts its groups is still
+// valid, it just has no arrangements
+fn row_to_config(row: &str) -> Result<Config, String> {
+  let (template, groups) = match row.split_whitespace().collect::<Vec<&str>>()[..] {
+    [template, groups] => (template, groups),
+    [_] => return Err(format!("`{}` has no groups", row.trim())),
+    [_, _, extra, ..] => return Err(format!("unexpected `{}` after the groups", extra)),
+    [] => return Err("empty row".to_string()),
+  };
+  if let Some((column, cell)) = template.chars().enumerate().find(|(_, cell)| !matches!(cell, '#' | '.' | '?')) {
+    return Err(format!("invalid cell `{}` at column {}, expected `#`, `.` or `?`", cell, column + 1));
+  }
+  let groups = groups
+    .split(',')
+    .enumerate()
+    .map(|(i, size)| match size.parse::<usize>() {
+      Ok(0) => Err(format!("group {} has length 0", i + 1)),
+      Ok(size) => Ok(size),
+      Err(_) => Err(format!("invalid group `{}`", size)),
+    })
+    .collect::<Result<_, _>>()?;
+  Ok(Config {
+    row: template.to_string(),
+    groups,
+  })
 }
 
-fn count_broken(row: &String) -> usize {
-  row.chars().filter(|c| c == &'#').count().try_into().unwrap()
+// counted with the dp rather than by listing them, which takes time
+// exponential in the number of unknowns
+fn count_valid(config: Config) -> Result<u128, String> {
+  if get_missing(&config).is_none() {
+    return Ok(0);
+  }
+  let num_valid = forced::total(&config)?;
+  // println!("# Valid: {}", num_valid);
+  Ok(num_valid)
 }
 
-fn count_unknown(row: &String) -> usize {
-  row.chars().filter(|c| c == &'?').count().try_into().unwrap()
+fn count_broken(row: &str) -> usize {
+  row.chars().filter(|c| c == &'#').count()
 }
 
-fn get_missing(config: &Config) -> usize {
+// None when the row already has more # than the groups allow, so there's no
+// arrangement at all
+fn get_missing(config: &Config) -> Option<usize> {
   let num_broken = count_broken(&config.row);
   let expected = config.groups.iter().sum::<usize>();
-  // println!("broken: {}, expected: {}", num_broken, expected);
-  return expected - num_broken;
-}
-
-fn substitute_template(row: &String, insertions: &[char]) -> String {
-    if insertions.is_empty() {
-      return row.to_owned();
-    }
-    let mut iter = row.chars();
-    let first = iter.next().unwrap();
-    let rest: String = iter.collect();
-    match first {
-      '?' => {
-        insertions.get(0).unwrap().to_string() + &substitute_template(&rest, &insertions[1..])
-      }
-      _ => {
-        first.to_string() + &substitute_template(&rest, insertions)
-      }
-    }
-}
-
-fn gen_row(config: &Config) -> Vec<String> {
-  let subs = gen(count_unknown(&config.row), get_missing(&config));
-  subs.iter().map(|insertions| substitute_template(&config.row, insertions)).collect()
-}
-
-fn gen(num_unknown: usize, num_missing: usize) -> Vec<Vec<char>> {
-  if (num_unknown == 0 && num_missing > 0) {
-    return vec![]
-  } else if num_missing == 0 {
-    return vec![vec!['.'; num_unknown]]
-  } else if num_unknown == num_missing {
-    return vec![vec!['#'; num_unknown]]
-  } else {
-    let mut broke = gen(num_unknown - 1, num_missing - 1);
-    broke.iter_mut().for_each(|v| v.insert(0, '#'));
-    let mut woke = gen(num_unknown - 1, num_missing);
-    woke.iter_mut().for_each(|v| v.insert(0, '.'));
-    broke.append(&mut woke);
-    return broke;
-  }
+  expected.checked_sub(num_broken)
 }
 
-fn check(config: String,  groups: &Vec<usize>) -> bool {
-  return &get_groups(config, 0) == groups
+// only the tests still check whole rows one at a time, against the search and
+// the dp
+#[cfg(test)]
+fn check(config: String, groups: &[usize]) -> bool {
+  get_groups(config, 0).is_ok_and(|found| found == groups)
 }
 
-fn get_groups(config: String, running: usize) -> Vec<usize> {
+// the groups of a row with no unknowns left; any other cell is an error
+#[cfg(test)]
+fn get_groups(config: String, running: usize) -> Result<Vec<usize>, String> {
   if config.is_empty() {
     if running > 0 {
-      return Vec::from([running]);
+      return Ok(Vec::from([running]));
     } else {
-      return Vec::new();
+      return Ok(Vec::new());
     }
   }
   let mut iter = config.chars();
@@ -104,17 +127,371 @@ fn get_groups(config: String, running: usize) -> Vec<usize> {
       get_groups(rest, running + 1)
     }
     '.' => {
-      let mut rest_vec = get_groups(rest, 0);
+      let mut rest_vec = get_groups(rest, 0)?;
       if running > 0 {
         rest_vec.insert(0, running);
-        rest_vec
+        Ok(rest_vec)
       } else{
-        rest_vec
+        Ok(rest_vec)
+      }
+    }
+    cell => {
+      Err(format!("`{}` is neither `#` nor `.`", cell))
+    }
+  }
+}
+
+// with `group` groups finished and a run of `run` #s so far, the groups and
+// run after one more cell, if they can still match
+fn step(groups: &[usize], group: usize, run: usize, cell: char) -> Option<(usize, usize)> {
+  match cell {
+    '#' if group < groups.len() && run < groups[group] => Some((group, run + 1)),
+    '#' => None,
+    _ if run == 0 => Some((group, 0)),
+    _ if run == groups[group] => Some((group + 1, 0)),
+    _ => None,
+  }
+}
+
+// whether a whole row ending with this many groups and run matches
+fn finished(groups: &[usize], group: usize, run: usize) -> bool {
+  if run == 0 {
+    group == groups.len()
+  } else {
+    group + 1 == groups.len() && run == groups[group]
+  }
+}
+
+// the valid arrangements of a row, `#` tried before `.` at each unknown,
+// found by a depth first search that gives up on a prefix as soon as its
+// groups stop matching or the rest of the row is too short for the groups
+// still to come
+struct Arrangements<'a> {
+  template: Vec<char>,
+  groups: &'a [usize],
+  // the arrangement being built; only the cells before the top of the stack
+  // are meaningful
+  row: Vec<char>,
+  // cells still to try: (index, group, run, cell) puts `cell` at `index`
+  // with `group` groups finished and a run of `run` #s before it
+  stack: Vec<(usize, usize, usize, char)>,
+  // an empty row has no cells to search but still one arrangement
+  empty_pending: bool,
+}
+
+impl<'a> Arrangements<'a> {
+  fn new(config: &'a Config) -> Self {
+    let template: Vec<char> = config.row.chars().collect();
+    let mut arrangements = Arrangements {
+      row: template.clone(),
+      empty_pending: template.is_empty() && config.groups.is_empty(),
+      template,
+      groups: &config.groups,
+      stack: Vec::new(),
+    };
+    if !arrangements.template.is_empty() {
+      arrangements.push_cell(0, 0, 0);
+    }
+    arrangements
+  }
+
+  fn push_cell(&mut self, index: usize, group: usize, run: usize) {
+    match self.template[index] {
+      '?' => {
+        self.stack.push((index, group, run, '.'));
+        self.stack.push((index, group, run, '#'));
+      }
+      cell => self.stack.push((index, group, run, cell)),
+    }
+  }
+
+  // whether the cells from `index` on can still fit the remaining groups
+  fn fits(&self, index: usize, group: usize, run: usize) -> bool {
+    let Some(current) = self.groups.get(group) else {
+      return true;
+    };
+    let rest: usize = self.groups[group + 1..].iter().map(|size| size + 1).sum();
+    current - run + rest <= self.template.len() - index
+  }
+}
+
+impl Iterator for Arrangements<'_> {
+  type Item = String;
+
+  fn next(&mut self) -> Option<String> {
+    if self.empty_pending {
+      self.empty_pending = false;
+      return Some(String::new());
+    }
+    while let Some((index, group, run, cell)) = self.stack.pop() {
+      self.row[index] = cell;
+      let Some((group, run)) = step(self.groups, group, run, cell) else {
+        continue;
+      };
+      if index + 1 == self.template.len() {
+        if finished(self.groups, group, run) {
+          return Some(self.row.iter().collect());
+        }
+      } else if self.fits(index + 1, group, run) {
+        self.push_cell(index + 1, group, run);
+      }
+    }
+    None
+  }
+}
+
+// the first `limit` arrangements of a row like `???.### 1,1,3`, one per line
+pub fn arrangements(row: &str, limit: Option<usize>) -> Result<String, String> {
+  let config = row_to_config(row)?;
+  let arrangements: Vec<String> = Arrangements::new(&config).take(limit.unwrap_or(usize::MAX)).collect();
+  Ok(arrangements.join("\n"))
+}
+
+// `count` arrangements drawn uniformly at random, repeats included, so the same
+// seed always gives the same draws
+pub fn sample_arrangements(row: &str, count: usize, seed: u64) -> Result<String, String> {
+  let config = row_to_config(row)?;
+  let sampler = sample::Sampler::new(&config)?;
+  let mut rng = StdRng::seed_from_u64(seed);
+  let samples: Option<Vec<String>> = (0..count).map(|_| sampler.sample(&mut rng)).collect();
+  samples.map(|samples| samples.join("\n")).ok_or("no valid arrangements".to_string())
+}
+
+// the row with every cell that's the same in all arrangements filled in,
+// followed by how many arrangements have each unknown cell damaged or not
+pub fn forced_cells(row: &str) -> Result<String, String> {
+  let config = row_to_config(row)?;
+  let counts = forced::cell_counts(&config)?;
+  let total = forced::total(&config)?;
+  if total == 0 {
+    return Ok(format!("{}\nno valid arrangements", config.row));
+  }
+  let annotated: String = counts.iter().map(|counts| counts.forced().unwrap_or('?')).collect();
+  let mut out = format!("{}\n{}\n{} arrangements", config.row, annotated, total);
+  for (i, (cell, counts)) in config.row.chars().zip(&counts).enumerate() {
+    if cell == '?' {
+      out.push_str(&format!(
+        "\ncell {}: {} damaged, {} operational{}",
+        i + 1,
+        counts.damaged,
+        counts.operational,
+        counts.forced().map_or(String::new(), |forced| format!(" (always {})", forced))
+      ));
+    }
+  }
+  Ok(out)
+}
+
+pub fn solve_nonogram(path: &str) -> Result<String, String> {
+  let input = std::fs::read_to_string(path).map_err(|err| format!("unable to read {}: {}", path, err))?;
+  let puzzle = nonogram::parse(&input).map_err(|err| format!("{}: {}", path, err))?;
+  Ok(match nonogram::solve(&puzzle)? {
+    nonogram::Solutions::None => "no solution".to_string(),
+    nonogram::Solutions::One(grid) => format!("unique solution\n{}", nonogram::render(&grid)),
+    nonogram::Solutions::Many(first, second) => format!(
+      "multiple solutions, two of them:\n{}\n\n{}",
+      nonogram::render(&first),
+      nonogram::render(&second)
+    ),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::{rngs::StdRng, Rng, SeedableRng};
+
+  use super::*;
+
+  const SEEDS: u64 = 1000;
+
+  fn random_config(rng: &mut StdRng, max_len: usize, cells: &[char]) -> Config {
+    let len = rng.random_range(0..max_len);
+    let row = (0..len).map(|_| cells[rng.random_range(0..cells.len())]).collect();
+    let groups = (0..rng.random_range(0..4)).map(|_| rng.random_range(1..4)).collect();
+    Config { row, groups }
+  }
+
+  // random short rows where the per-cell counts have to agree with tallying
+  // every enumerated arrangement
+  #[test]
+  fn cell_counts_match_enumeration() {
+    for seed in 0..SEEDS {
+      let mut rng = StdRng::seed_from_u64(seed);
+      let config = random_config(&mut rng, 12, &['#', '.', '?', '?']);
+      let mut expected = vec![forced::CellCounts::default(); config.row.len()];
+      let mut total = 0;
+      for arrangement in Arrangements::new(&config) {
+        total += 1;
+        for (counts, cell) in expected.iter_mut().zip(arrangement.chars()) {
+          if cell == '#' {
+            counts.damaged += 1;
+          } else {
+            counts.operational += 1;
+          }
+        }
+      }
+      assert_eq!(forced::cell_counts(&config), Ok(expected), "seed {}: {} {:?}", seed, config.row, config.groups);
+      assert_eq!(forced::total(&config), Ok(total), "seed {}: {} {:?}", seed, config.row, config.groups);
+    }
+  }
+
+  // the search against trying every way of filling in the unknowns and
+  // checking each row on its own
+  #[test]
+  fn enumeration_matches_brute_force() {
+    for seed in 0..SEEDS {
+      let mut rng = StdRng::seed_from_u64(seed);
+      let config = random_config(&mut rng, 10, &['#', '.', '?', '?']);
+      let unknowns = config.row.chars().filter(|cell| *cell == '?').count();
+      let mut expected = Vec::new();
+      for mask in 0..1u32 << unknowns {
+        let mut bit = unknowns;
+        let row: String = config
+          .row
+          .chars()
+          .map(|cell| match cell {
+            '?' => {
+              bit -= 1;
+              if mask >> bit & 1 == 1 { '#' } else { '.' }
+            }
+            cell => cell,
+          })
+          .collect();
+        if check(row.clone(), &config.groups) {
+          expected.push(row);
+        }
+      }
+      let mut found: Vec<String> = Arrangements::new(&config).collect();
+      found.sort();
+      expected.sort();
+      assert_eq!(found, expected, "seed {}: {} {:?}", seed, config.row, config.groups);
+    }
+  }
+
+  // draws from random rows with a handful of arrangements: every draw has to be
+  // one of them, and a chi-squared test over 200 draws per arrangement has to
+  // accept that they're equally likely; the cutoff sits five standard deviations
+  // out, so a correct sampler essentially never trips it while one that picks
+  // each unknown cell by a coin flip fails on most uneven rows
+  #[test]
+  fn samples_are_uniform() {
+    for seed in 0..SEEDS {
+      let mut rng = StdRng::seed_from_u64(seed);
+      let config = random_config(&mut rng, 10, &['#', '.', '?', '?', '?']);
+      let case = format!("seed {}: {} {:?}", seed, config.row, config.groups);
+      let expected: Vec<String> = Arrangements::new(&config).collect();
+      let sampler = sample::Sampler::new(&config).unwrap();
+      assert_eq!(sampler.total(), expected.len() as u128, "{}", case);
+      if expected.is_empty() {
+        assert_eq!(sampler.sample(&mut rng), None, "{}", case);
+        continue;
+      }
+      if expected.len() > 30 {
+        continue;
       }
+      let per_arrangement = 200;
+      let mut seen = vec![0; expected.len()];
+      for _ in 0..per_arrangement * expected.len() {
+        let drawn = sampler.sample(&mut rng).unwrap();
+        let index = expected.iter().position(|arrangement| *arrangement == drawn);
+        assert!(index.is_some(), "{}: drew invalid {}", case, drawn);
+        seen[index.unwrap()] += 1;
+      }
+      let chi_squared: f64 = seen
+        .iter()
+        .map(|count| (*count as f64 - per_arrangement as f64).powi(2) / per_arrangement as f64)
+        .sum();
+      // wilson-hilferty's approximation of the chi-squared quantile
+      let freedom = (expected.len() - 1) as f64;
+      let cutoff = if freedom == 0.0 {
+        0.0
+      } else {
+        let spread = 2.0 / (9.0 * freedom);
+        freedom * (1.0 - spread + 5.0 * spread.sqrt()).powi(3)
+      };
+      assert!(
+        chi_squared <= cutoff,
+        "{}: chi-squared {:.1} over cutoff {:.1}, counts {:?}",
+        case, chi_squared, cutoff, seen
+      );
+    }
+  }
+
+  // the clues of a random grid: whatever the solver finds has to have the same
+  // clues, and a unique solution has to be the grid itself
+  #[test]
+  fn nonogram_solutions_match_the_clues() {
+    for seed in 0..SEEDS {
+      let mut rng = StdRng::seed_from_u64(seed);
+      let height = rng.random_range(1..7);
+      let width = rng.random_range(1..7);
+      let grid: nonogram::Grid = (0..height)
+        .map(|_| (0..width).map(|_| if rng.random_bool(0.55) { '#' } else { '.' }).collect())
+        .collect();
+      let puzzle = nonogram::clues(&grid);
+      let case = format!("seed {} for\n{}", seed, nonogram::render(&grid));
+      match nonogram::solve(&puzzle).unwrap() {
+        nonogram::Solutions::None => panic!("no solution, {}", case),
+        nonogram::Solutions::One(found) => assert_eq!(found, grid, "{}", case),
+        nonogram::Solutions::Many(first, second) => {
+          assert_ne!(first, second, "{}", case);
+          assert_eq!(nonogram::clues(&first), puzzle, "{}", case);
+          assert_eq!(nonogram::clues(&second), puzzle, "{}", case);
+        }
+      }
+    }
+  }
+
+  // records that used to panic or underflow: malformed ones have to be rejected
+  // with their reason, contradictory ones have to count no arrangements
+  #[test]
+  fn records() {
+    let counts = [
+      ("???.### 1,1,3", 1),
+      (".??..??...?##. 1,1,3", 4),
+      ("###.### 1", 0),
+      ("#### 1,1", 0),
+      ("..#.. 1", 1),
+      ("??? 1 ", 3),
+    ];
+    for (line, count) in counts {
+      let config = row_to_config(line).unwrap();
+      assert_eq!(Arrangements::new(&config).count(), count, "{}", line);
+      assert_eq!(count_valid(config), Ok(count as u128), "{}", line);
     }
-    _ => {
-      panic!();
+    let rejected = [
+      ("???.###", "has no groups"),
+      ("???.### 1,1,3 extra", "unexpected `extra` after the groups"),
+      ("??x.### 1,1,3", "invalid cell `x` at column 3"),
+      ("???.### 1,0,3", "group 2 has length 0"),
+      ("???.### 1,,3", "invalid group ``"),
+      ("???.### 1,-1", "invalid group `-1`"),
+    ];
+    for (line, reason) in rejected {
+      match row_to_config(line) {
+        Ok(config) => panic!("`{}` gave {:?}", line, config.groups),
+        Err(err) => assert!(err.contains(reason), "`{}` gave {}", line, err),
+      }
     }
+    assert_eq!(get_missing(&row_to_config("###.### 1").unwrap()), None);
+    assert!(get_groups("#.?".to_string(), 0).is_err());
   }
-  // todo!()
-}
\ No newline at end of file
+
+  // the same records through the line reader: blank lines are skipped and
+  // every bad line is reported with its number
+  #[test]
+  fn streamed_records() {
+    let input = "???.### 1,1,3\n\n.??..??...?##. 1,1,3\n###.### 1\n";
+    assert_eq!(total_arrangements(input.as_bytes()), Ok(5));
+    let input = "???.### 1,1,3\n???.###\n\n???.### 1,0,3\n??? 1\n";
+    assert_eq!(
+      total_arrangements(input.as_bytes()),
+      Err(vec!["line 2: `???.###` has no groups".to_string(), "line 4: group 2 has length 0".to_string()])
+    );
+    let input: &[u8] = b"??? 1\n\xff 1\n??? 1\n";
+    assert_eq!(
+      total_arrangements(input).map_err(|errors| errors.len()),
+      Err(1)
+    );
+  }
+}
@@ -0,0 +1,130 @@
+use super::{finished, step, Config};
+
+// how many valid arrangements have a cell damaged and how many operational
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(super) struct CellCounts {
+  pub(super) damaged: u128,
+  pub(super) operational: u128,
+}
+
+impl CellCounts {
+  // the cell's value in every arrangement, if there are any and they agree
+  pub(super) fn forced(&self) -> Option<char> {
+    match (self.damaged, self.operational) {
+      (0, 0) => None,
+      (_, 0) => Some('#'),
+      (0, _) => Some('.'),
+      _ => None,
+    }
+  }
+}
+
+const TOO_MANY: &str = "too many arrangements to count in a u128";
+
+// the (group, run) pairs step can reach, numbered so they can index a table;
+// `group` runs up to groups.len(), where the run is always 0
+pub(super) struct States<'a> {
+  pub(super) groups: &'a [usize],
+  offsets: Vec<usize>,
+}
+
+impl<'a> States<'a> {
+  pub(super) fn new(groups: &'a [usize]) -> Self {
+    let mut offsets = vec![0];
+    for size in groups {
+      offsets.push(offsets.last().unwrap() + size + 1);
+    }
+    States { groups, offsets }
+  }
+
+  fn len(&self) -> usize {
+    self.offsets.last().unwrap() + 1
+  }
+
+  pub(super) fn index(&self, group: usize, run: usize) -> usize {
+    self.offsets[group] + run
+  }
+
+  fn all(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (0..=self.groups.len()).flat_map(move |group| {
+      let max_run = self.groups.get(group).copied().unwrap_or(0);
+      (0..=max_run).map(move |run| (group, run))
+    })
+  }
+}
+
+pub(super) fn options(cell: char) -> &'static [char] {
+  match cell {
+    '#' => &['#'],
+    '.' => &['.'],
+    _ => &['#', '.'],
+  }
+}
+
+fn add(a: u128, b: u128) -> Result<u128, String> {
+  a.checked_add(b).ok_or(TOO_MANY.to_string())
+}
+
+// backward[i][state]: the ways to fill cells i.. from `state` into a valid row
+pub(super) fn backward(row: &[char], states: &States) -> Result<Vec<Vec<u128>>, String> {
+  let mut backward = vec![vec![0; states.len()]; row.len() + 1];
+  for (group, run) in states.all() {
+    backward[row.len()][states.index(group, run)] = finished(states.groups, group, run) as u128;
+  }
+  for i in (0..row.len()).rev() {
+    for (group, run) in states.all() {
+      let mut ways = 0;
+      for cell in options(row[i]) {
+        if let Some((group, run)) = step(states.groups, group, run, *cell) {
+          ways = add(ways, backward[i + 1][states.index(group, run)])?;
+        }
+      }
+      backward[i][states.index(group, run)] = ways;
+    }
+  }
+  Ok(backward)
+}
+
+pub(super) fn total(config: &Config) -> Result<u128, String> {
+  let row: Vec<char> = config.row.chars().collect();
+  let states = States::new(&config.groups);
+  Ok(backward(&row, &states)?[0][states.index(0, 0)])
+}
+
+// for every cell, the arrangements through it split by its value: the ways to
+// reach each state before the cell times the ways to finish after it, with
+// no arrangement ever built
+pub(super) fn cell_counts(config: &Config) -> Result<Vec<CellCounts>, String> {
+  let row: Vec<char> = config.row.chars().collect();
+  let states = States::new(&config.groups);
+  let backward = backward(&row, &states)?;
+  let mut forward = vec![0; states.len()];
+  forward[states.index(0, 0)] = 1;
+  let mut counts = Vec::new();
+  for i in 0..row.len() {
+    let mut cell_counts = CellCounts::default();
+    let mut next = vec![0; states.len()];
+    for (group, run) in states.all() {
+      let ways = forward[states.index(group, run)];
+      if ways == 0 {
+        continue;
+      }
+      for cell in options(row[i]) {
+        let Some((group, run)) = step(states.groups, group, run, *cell) else {
+          continue;
+        };
+        let state = states.index(group, run);
+        next[state] = add(next[state], ways)?;
+        let through = ways.checked_mul(backward[i + 1][state]).ok_or(TOO_MANY.to_string())?;
+        if *cell == '#' {
+          cell_counts.damaged = add(cell_counts.damaged, through)?;
+        } else {
+          cell_counts.operational = add(cell_counts.operational, through)?;
+        }
+      }
+    }
+    counts.push(cell_counts);
+    forward = next;
+  }
+  Ok(counts)
+}
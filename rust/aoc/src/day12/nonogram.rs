@@ -0,0 +1,192 @@
+use super::{forced, Config};
+
+// a puzzle's clues, one list of group sizes per row and per column
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Puzzle {
+  pub(super) rows: Vec<Vec<usize>>,
+  pub(super) columns: Vec<Vec<usize>>,
+}
+
+// cells are `#`, `.` or `?` like a day 12 row
+pub(super) type Grid = Vec<Vec<char>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Solutions {
+  None,
+  One(Grid),
+  // two of them, which is enough to show the puzzle is ambiguous
+  Many(Grid, Grid),
+}
+
+fn parse_clue(line: &str, number: usize) -> Result<Vec<usize>, String> {
+  let groups: Vec<usize> = line
+    .split(|c: char| c == ',' || c.is_whitespace())
+    .filter(|size| !size.is_empty())
+    .map(|size| size.parse().map_err(|_| format!("line {}: invalid clue `{}`", number, line)))
+    .collect::<Result<_, _>>()?;
+  // `0` is the usual way to write a line with no groups
+  Ok(groups.into_iter().filter(|size| *size > 0).collect())
+}
+
+// the `.non` format: `width` and `height` lines, then a `rows` and a `columns`
+// line each followed by one clue per line (`1,3`, or `0` for none); other
+// keys like `title` or `goal` are skipped
+pub(super) fn parse(input: &str) -> Result<Puzzle, String> {
+  let mut width = None;
+  let mut height = None;
+  let mut rows = None;
+  let mut columns = None;
+  let mut lines = input.lines().enumerate().map(|(i, line)| (i + 1, line.trim())).peekable();
+  while let Some((number, line)) = lines.next() {
+    let mut words = line.split_whitespace();
+    let Some(key) = words.next() else {
+      continue;
+    };
+    let value = |name: &str| -> Result<usize, String> {
+      words
+        .clone()
+        .next()
+        .and_then(|value| value.parse().ok())
+        .ok_or(format!("line {}: {} needs a number", number, name))
+    };
+    match key {
+      "width" => width = Some(value("width")?),
+      "height" => height = Some(value("height")?),
+      "rows" | "columns" => {
+        let mut clues = Vec::new();
+        while let Some((number, line)) = lines.next_if(|(_, line)| line.starts_with(|c: char| c.is_ascii_digit())) {
+          clues.push(parse_clue(line, number)?);
+        }
+        if key == "rows" {
+          rows = Some(clues);
+        } else {
+          columns = Some(clues);
+        }
+      }
+      _ => {}
+    }
+  }
+  let rows = rows.ok_or("missing rows".to_string())?;
+  let columns = columns.ok_or("missing columns".to_string())?;
+  if height.is_some_and(|height| height != rows.len()) {
+    return Err(format!("height is {} but there are {} row clues", height.unwrap(), rows.len()));
+  }
+  if width.is_some_and(|width| width != columns.len()) {
+    return Err(format!("width is {} but there are {} column clues", width.unwrap(), columns.len()));
+  }
+  Ok(Puzzle { rows, columns })
+}
+
+// the clues a filled in grid would have
+#[cfg(test)]
+pub(super) fn clues(grid: &Grid) -> Puzzle {
+  let groups = |line: Vec<char>| -> Vec<usize> {
+    line.split(|cell| *cell != '#').map(|group| group.len()).filter(|size| *size > 0).collect()
+  };
+  let width = grid.first().map_or(0, |row| row.len());
+  Puzzle {
+    rows: grid.iter().map(|row| groups(row.clone())).collect(),
+    columns: (0..width).map(|x| groups(grid.iter().map(|row| row[x]).collect())).collect(),
+  }
+}
+
+// fills in every cell one line forces, until nothing changes; false when some
+// line has no arrangement left
+fn propagate(grid: &mut Grid, puzzle: &Puzzle) -> Result<bool, String> {
+  let height = puzzle.rows.len();
+  let width = puzzle.columns.len();
+  let mut dirty_rows = vec![true; height];
+  let mut dirty_columns = vec![true; width];
+  loop {
+    let mut changed = false;
+    for y in 0..height {
+      if !std::mem::take(&mut dirty_rows[y]) {
+        continue;
+      }
+      let config = Config { row: grid[y].iter().collect(), groups: puzzle.rows[y].clone() };
+      let counts = forced::cell_counts(&config)?;
+      if forced::total(&config)? == 0 {
+        return Ok(false);
+      }
+      for (x, counts) in counts.iter().enumerate() {
+        if let (Some(cell), '?') = (counts.forced(), grid[y][x]) {
+          grid[y][x] = cell;
+          dirty_columns[x] = true;
+          changed = true;
+        }
+      }
+    }
+    for x in 0..width {
+      if !std::mem::take(&mut dirty_columns[x]) {
+        continue;
+      }
+      let config = Config { row: grid.iter().map(|row| row[x]).collect(), groups: puzzle.columns[x].clone() };
+      let counts = forced::cell_counts(&config)?;
+      if forced::total(&config)? == 0 {
+        return Ok(false);
+      }
+      for (y, counts) in counts.iter().enumerate() {
+        if let (Some(cell), '?') = (counts.forced(), grid[y][x]) {
+          grid[y][x] = cell;
+          dirty_rows[y] = true;
+          changed = true;
+        }
+      }
+    }
+    if !changed {
+      return Ok(true);
+    }
+  }
+}
+
+// propagates, then guesses the first unknown cell both ways; stops once two
+// solutions are found
+fn search(mut grid: Grid, puzzle: &Puzzle, found: &mut Vec<Grid>) -> Result<(), String> {
+  if !propagate(&mut grid, puzzle)? {
+    return Ok(());
+  }
+  let unknown = grid.iter().enumerate().find_map(|(y, row)| row.iter().position(|cell| *cell == '?').map(|x| (y, x)));
+  let Some((y, x)) = unknown else {
+    found.push(grid);
+    return Ok(());
+  };
+  for cell in ['#', '.'] {
+    if found.len() == 2 {
+      break;
+    }
+    let mut guess = grid.clone();
+    guess[y][x] = cell;
+    search(guess, puzzle, found)?;
+  }
+  Ok(())
+}
+
+pub(super) fn solve(puzzle: &Puzzle) -> Result<Solutions, String> {
+  let grid = vec![vec!['?'; puzzle.columns.len()]; puzzle.rows.len()];
+  let mut found = Vec::new();
+  search(grid, puzzle, &mut found)?;
+  let mut found = found.into_iter();
+  Ok(match (found.next(), found.next()) {
+    (None, _) => Solutions::None,
+    (Some(grid), None) => Solutions::One(grid),
+    (Some(first), Some(second)) => Solutions::Many(first, second),
+  })
+}
+
+// two characters a cell so the grid comes out roughly square
+pub(super) fn render(grid: &Grid) -> String {
+  grid
+    .iter()
+    .map(|row| {
+      row
+        .iter()
+        .map(|cell| match cell {
+          '#' => "██",
+          '.' => "··",
+          _ => "??",
+        })
+        .collect::<String>()
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
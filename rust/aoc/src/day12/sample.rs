@@ -0,0 +1,55 @@
+use rand::Rng;
+
+use super::{
+  forced::{self, States},
+  step, Config,
+};
+
+// draws valid arrangements uniformly without listing them: walking the row
+// left to right, each unknown cell takes a value with probability proportional
+// to the number of ways the rest of the row can still be finished after it
+pub(super) struct Sampler<'a> {
+  row: Vec<char>,
+  states: States<'a>,
+  backward: Vec<Vec<u128>>,
+}
+
+impl<'a> Sampler<'a> {
+  pub(super) fn new(config: &'a Config) -> Result<Self, String> {
+    let row: Vec<char> = config.row.chars().collect();
+    let states = States::new(&config.groups);
+    let backward = forced::backward(&row, &states)?;
+    Ok(Sampler { row, states, backward })
+  }
+
+  // how many arrangements there are to draw from
+  pub(super) fn total(&self) -> u128 {
+    self.backward[0][self.states.index(0, 0)]
+  }
+
+  // None when the row has no valid arrangement
+  pub(super) fn sample(&self, rng: &mut impl Rng) -> Option<String> {
+    if self.total() == 0 {
+      return None;
+    }
+    let (mut group, mut run) = (0, 0);
+    let mut arrangement = String::with_capacity(self.row.len());
+    for i in 0..self.row.len() {
+      let ways = self.backward[i][self.states.index(group, run)];
+      let mut pick = rng.random_range(0..ways);
+      for cell in forced::options(self.row[i]) {
+        let Some((next_group, next_run)) = step(self.states.groups, group, run, *cell) else {
+          continue;
+        };
+        let through = self.backward[i + 1][self.states.index(next_group, next_run)];
+        if pick < through {
+          arrangement.push(*cell);
+          (group, run) = (next_group, next_run);
+          break;
+        }
+        pick -= through;
+      }
+    }
+    Some(arrangement)
+  }
+}
@@ -1,26 +1,49 @@
-use regex::Regex;
 use std::{
     cmp::{max, min},
     collections::HashMap,
-    fs,
+    fmt, fs,
+    io::{self, BufRead, BufReader},
+    time::Instant,
 };
 
+use crate::input;
+use parse::ParseError;
+use rand::{rngs::StdRng, SeedableRng};
+
+mod batch;
+mod compile;
+mod diff;
+mod fuzz;
+mod generate;
+mod memo;
+mod optimize;
+mod parse;
+mod print;
+mod query;
+mod reach;
+mod repl;
+mod trace;
+mod validate;
+
+#[cfg(test)]
+mod tests;
+
 type WorkflowName = String;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum WorkFlowResult {
     Accepted,
     Rejected,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 enum Action {
     Accept,
     Reject,
     Switch(WorkflowName),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 enum Category {
     X,
     M,
@@ -28,21 +51,22 @@ enum Category {
     S,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 enum Comparison {
     LessThan,
     GreaterThan,
 }
 
-#[derive(Debug)]
-struct Toy {
-    x: u64,
-    m: u64,
-    a: u64,
-    s: u64,
+// a part and its four ratings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toy {
+    pub x: u64,
+    pub m: u64,
+    pub a: u64,
+    pub s: u64,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 struct BoundedToy {
     x: Bounded,
     m: Bounded,
@@ -50,25 +74,117 @@ struct BoundedToy {
     s: Bounded,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct Rule {
     category: Category,
     comparison: Comparison,
     val: u64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct WorkFlow {
     rules: Vec<(Rule, Action)>,
     default: Action,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 struct Bounded {
     min: u64,
     max: u64,
 }
 
+impl fmt::Display for WorkFlowResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkFlowResult::Accepted => write!(f, "Accepted"),
+            WorkFlowResult::Rejected => write!(f, "Rejected"),
+        }
+    }
+}
+
+impl fmt::Display for Toy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{x={},m={},a={},s={}}}", self.x, self.m, self.a, self.s)
+    }
+}
+
+impl fmt::Display for Bounded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..={}", self.min, self.max)
+    }
+}
+
+impl fmt::Display for BoundedToy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "x={} m={} a={} s={}", self.x, self.m, self.a, self.s)
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Accept => write!(f, "A"),
+            Action::Reject => write!(f, "R"),
+            Action::Switch(workflow_name) => write!(f, "{}", workflow_name),
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Category::X => write!(f, "x"),
+            Category::M => write!(f, "m"),
+            Category::A => write!(f, "a"),
+            Category::S => write!(f, "s"),
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Comparison::LessThan => write!(f, "<"),
+            Comparison::GreaterThan => write!(f, ">"),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.category, self.comparison, self.val)
+    }
+}
+
+// a combination count that doesn't fit in a u128
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CountOverflow;
+
+impl fmt::Display for CountOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "number of combinations overflows u128")
+    }
+}
+
+// why solve_from couldn't count from the requested entry workflow
+#[derive(Debug, Clone, PartialEq)]
+enum SolveError {
+    Invalid(validate::ValidationError),
+    Overflow(CountOverflow),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Invalid(validate::ValidationError::MissingEntry(entry)) => {
+                write!(f, "unknown entry workflow {}", entry)
+            }
+            SolveError::Invalid(err) => write!(f, "{}", err),
+            SolveError::Overflow(err) => write!(f, "{}", err),
+        }
+    }
+}
+
 impl Default for Bounded {
     fn default() -> Self {
         Bounded { min: 1, max: 4000 }
@@ -96,19 +212,28 @@ fn constrain_inverse(
     constraint: &Comparison,
     constraint_val: u64,
 ) -> Option<Bounded> {
+    // at the ends of u64 the inverse covers everything: every value is <= u64::MAX
+    // and >= 0
     match constraint {
-        Comparison::GreaterThan => constrain(val, &Comparison::LessThan, constraint_val + 1),
-        Comparison::LessThan => constrain(val, &Comparison::GreaterThan, constraint_val - 1),
+        Comparison::GreaterThan => match constraint_val.checked_add(1) {
+            Some(bound) => constrain(val, &Comparison::LessThan, bound),
+            None => Some(val.clone()),
+        },
+        Comparison::LessThan => match constraint_val.checked_sub(1) {
+            Some(bound) => constrain(val, &Comparison::GreaterThan, bound),
+            None => Some(val.clone()),
+        },
     }
 }
 
 fn constrain(val: &Bounded, constraint: &Comparison, constraint_val: u64) -> Option<Bounded> {
-    if !is_feasible(&val, &constraint, constraint_val) {
-        return None;
+    if !is_feasible(val, constraint, constraint_val) {
+        None
     } else {
-        return match constraint {
+        match constraint {
             Comparison::GreaterThan => {
-                let new_min = max(val.min, constraint_val + 1);
+                // nothing is greater than u64::MAX
+                let new_min = max(val.min, constraint_val.checked_add(1)?);
                 let new_val = Bounded {
                     min: new_min,
                     max: val.max,
@@ -116,14 +241,15 @@ fn constrain(val: &Bounded, constraint: &Comparison, constraint_val: u64) -> Opt
                 assert_bounded_invariant(new_val)
             }
             Comparison::LessThan => {
-                let new_max = min(val.max, constraint_val - 1);
+                // nothing is less than 0
+                let new_max = min(val.max, constraint_val.checked_sub(1)?);
                 let new_val = Bounded {
                     min: val.min,
                     max: new_max,
                 };
                 assert_bounded_invariant(new_val)
             }
-        };
+        }
     }
     /*match constraint {
         Comparison::GreaterThan => {
@@ -177,9 +303,9 @@ fn handle_workflow(
     let WorkFlow { rules, default } = workflow;
     let rule = rules
         .iter()
-        .find(|(rule, _)| match_rule(rule, &toy))
+        .find(|(rule, _)| match_rule(rule, toy))
         .map(|(_, action)| action);
-    let action = rule.unwrap_or(&default);
+    let action = rule.unwrap_or(default);
     match action {
         Action::Accept => WorkFlowResult::Accepted,
         Action::Reject => WorkFlowResult::Rejected,
@@ -190,92 +316,25 @@ fn handle_workflow(
     }
 }
 
-fn sum(toy: &Toy) -> u64 {
-    toy.x + toy.m + toy.a + toy.s
-}
-
-fn handle_toy(workflows: &HashMap<WorkflowName, WorkFlow>, toy: Toy) -> u64 {
-    let in_wf = workflows.get("in").expect("missing in workflow");
-    let res = handle_workflow(workflows, in_wf, &toy);
-    // println!("Result for {:?}, {:?}", toy, res);
-    match res {
-        WorkFlowResult::Accepted => sum(&toy),
-        WorkFlowResult::Rejected => 0,
-    }
-}
-
-fn parse_category(cat: &str) -> Category {
-    match cat {
-        "x" => Category::X,
-        "m" => Category::M,
-        "a" => Category::A,
-        "s" => Category::S,
-        _ => panic!("unknown category"),
-    }
-}
-
-// px{a<2006:qkq,m>2090:A,rfg}
-fn parse_rule(rule: &str) -> (Rule, Action) {
-    let re =
-        Regex::new(r"(?<category>[xmas]+)(?<comparison>[<>])(?<val>[0-9]+):(?<action>[a-z]+|[AR])")
-            .unwrap();
-    let caps = re.captures(rule).unwrap();
-    let category = parse_category(&caps["category"]);
-    let val = caps["val"].parse::<u64>().expect("invalid value");
-    let comparison = if caps["comparison"].to_string() == ">" {
-        Comparison::GreaterThan
-    } else {
-        Comparison::LessThan
-    };
-    let action = parse_action(&caps["action"]);
-    return (
-        Rule {
-            category,
-            val,
-            comparison,
-        },
-        action,
-    );
+// wide enough that four u64 ratings can't overflow it
+fn sum(toy: &Toy) -> u128 {
+    toy.x as u128 + toy.m as u128 + toy.a as u128 + toy.s as u128
 }
 
-fn parse_action(action: &str) -> Action {
-    match action {
-        "A" => Action::Accept,
-        "R" => Action::Reject,
-        workflow_name => Action::Switch(workflow_name.to_string()),
-    }
+fn parse_workflow(workflow: &str) -> Result<(WorkflowName, WorkFlow), ParseError> {
+    parse::parse_workflow(workflow)
 }
 
-fn parse_workflow(workflow: &str) -> (WorkflowName, WorkFlow) {
-    let re = Regex::new(
-        r"(?<name>[a-z]+)\{(?<rules>(?<rule>([xmas][><][0-9]+):([RA]|[a-z]+),)+([RA]|[a-z]+))\}",
-    )
-    .unwrap();
-    let caps = re.captures(&workflow).unwrap();
-    let name = caps["name"].to_string();
-    let split_rules: Vec<&str> = caps["rules"].split(',').collect();
-    let rules: Vec<(Rule, Action)> = split_rules[0..split_rules.len() - 1]
-        .iter()
-        .map(|rule| parse_rule(rule))
-        .collect();
-    let default = parse_action(split_rules.last().expect("no default rule"));
-    let workflow = WorkFlow { rules, default };
-    return (name, workflow);
+fn parse_toy(toy: &str) -> Result<Toy, ParseError> {
+    parse::parse_toy(toy)
 }
 
-fn parse_toy(toy: &str) -> Toy {
-    let re =
-        Regex::new(r"(\{x=(?<x>[0-9]+),m=(?<m>[0-9]+),a=(?<a>[0-9]+),s=(?<s>[0-9]+)\})").unwrap();
-    let caps = re.captures(toy).unwrap();
-    let x = caps["x"].parse::<u64>().expect("invalid x value");
-    let m = caps["m"].parse::<u64>().expect("invalid m value");
-    let a = caps["a"].parse::<u64>().expect("invalid a value");
-    let s = caps["s"].parse::<u64>().expect("invalid s value");
-    return Toy { x, m, a, s };
+fn parse_workflows(input: &str) -> Result<HashMap<WorkflowName, WorkFlow>, ParseError> {
+    parse::parse_input(input).map(|input| input.workflows)
 }
 
-fn get_feasible_options(val: &Bounded) -> u64 {
-    return (val.max - val.min) + 1;
+fn get_feasible_options(val: &Bounded) -> u128 {
+    (val.max - val.min) as u128 + 1
     // match (val.min, val.max) {
     //   (Some(min), Some(max)) => {
     //     Some((max - min) + 1)
@@ -284,12 +343,110 @@ fn get_feasible_options(val: &Bounded) -> u64 {
     // }
 }
 
-fn get_toy_options(val: &BoundedToy) -> u64 {
+fn get_toy_options(val: &BoundedToy) -> Result<u128, CountOverflow> {
     let x = get_feasible_options(&val.x);
     let m = get_feasible_options(&val.m);
     let a = get_feasible_options(&val.a);
     let s = get_feasible_options(&val.s);
-    return x * m * a * s;
+    x.checked_mul(m)
+        .and_then(|xm| xm.checked_mul(a))
+        .and_then(|xma| xma.checked_mul(s))
+        .ok_or(CountOverflow)
+}
+
+fn add_options(a: u128, b: u128) -> Result<u128, CountOverflow> {
+    a.checked_add(b).ok_or(CountOverflow)
+}
+
+fn total_options<'a>(
+    toys: impl IntoIterator<Item = &'a BoundedToy>,
+) -> Result<u128, CountOverflow> {
+    toys.into_iter()
+        .try_fold(0, |acc, toy| add_options(acc, get_toy_options(toy)?))
+}
+
+// every rating ranging over min..=max
+fn domain(min: u64, max: u64) -> BoundedToy {
+    let val = Bounded { min, max };
+    BoundedToy {
+        x: val.clone(),
+        m: val.clone(),
+        a: val.clone(),
+        s: val,
+    }
+}
+
+// one bound on a region: x<100 and x>5 narrow it the same way a rule does,
+// x=1..=100 keeps an inclusive range and x=7 a single rating
+fn narrow(toy: &BoundedToy, condition: &str) -> Result<Option<BoundedToy>, String> {
+    let split = condition.find(['<', '>', '=']).ok_or(format!(
+        "expected a condition like x<100 or x=1..=100, got {}",
+        condition
+    ))?;
+    let category = match &condition[..split] {
+        "x" => Category::X,
+        "m" => Category::M,
+        "a" => Category::A,
+        "s" => Category::S,
+        category => return Err(format!("unknown category {} in {}", category, condition)),
+    };
+    let parse_val = |val: &str| -> Result<u64, String> {
+        val.parse()
+            .map_err(|_| format!("invalid value {} in {}", val, condition))
+    };
+    let rest = &condition[split + 1..];
+    match &condition[split..split + 1] {
+        "<" => {
+            let val = parse_val(rest)?;
+            Ok(constrain_toy(toy, &category, &Comparison::LessThan, val))
+        }
+        ">" => {
+            let val = parse_val(rest)?;
+            Ok(constrain_toy(toy, &category, &Comparison::GreaterThan, val))
+        }
+        _ => {
+            let (low, high) = match rest.split_once("..=") {
+                Some((low, high)) => (parse_val(low)?, parse_val(high)?),
+                None => (parse_val(rest)?, parse_val(rest)?),
+            };
+            // low - 1 and high + 1 don't exist at the ends of u64, and
+            // there's nothing to cut off there anyway
+            let toy = match low.checked_sub(1) {
+                Some(below) => constrain_toy(toy, &category, &Comparison::GreaterThan, below),
+                None => Some(toy.clone()),
+            };
+            Ok(match (toy, high.checked_add(1)) {
+                (Some(toy), Some(above)) => {
+                    constrain_toy(&toy, &category, &Comparison::LessThan, above)
+                }
+                (toy, _) => toy,
+            })
+        }
+    }
+}
+
+// the default region narrowed by every condition; None when nothing is left
+fn parse_region<'a>(
+    conditions: impl IntoIterator<Item = &'a str>,
+) -> Result<Option<BoundedToy>, String> {
+    let mut region = Some(BoundedToy::default());
+    for condition in conditions {
+        region = match region {
+            Some(toy) => narrow(&toy, condition)?,
+            None => None,
+        };
+    }
+    Ok(region)
+}
+
+// the smallest part in a region
+fn min_toy(toy: &BoundedToy) -> Toy {
+    Toy {
+        x: toy.x.min,
+        m: toy.m.min,
+        a: toy.a.min,
+        s: toy.s.min,
+    }
 }
 
 fn constrain_toy(
@@ -346,23 +503,72 @@ fn constrain_toy_inverse(
     }
 }
 
+// splits `toy` into disjoint boxes, each paired with the result every part in
+// it gets
+fn decided_boxes(
+    toy: &BoundedToy,
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    workflow: &WorkFlow,
+) -> Vec<(BoundedToy, WorkFlowResult)> {
+    let mut boxes = Vec::new();
+    let mut remaining = Some(toy.clone());
+    for (rule, action) in &workflow.rules {
+        if let Some(toy) = remaining {
+            if let Some(matched) = constrain_toy(&toy, &rule.category, &rule.comparison, rule.val) {
+                boxes.append(&mut action_boxes(&matched, workflows, action));
+            }
+            remaining = constrain_toy_inverse(&toy, &rule.category, &rule.comparison, rule.val);
+        }
+    }
+    if let Some(toy) = remaining {
+        boxes.append(&mut action_boxes(&toy, workflows, &workflow.default));
+    }
+    boxes
+}
+
+fn action_boxes(
+    toy: &BoundedToy,
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    action: &Action,
+) -> Vec<(BoundedToy, WorkFlowResult)> {
+    match action {
+        Action::Accept => vec![(toy.clone(), WorkFlowResult::Accepted)],
+        Action::Reject => vec![(toy.clone(), WorkFlowResult::Rejected)],
+        Action::Switch(new_wf) => decided_boxes(toy, workflows, &workflows[new_wf]),
+    }
+}
+
+// the disjoint boxes of `toy` that end up accepted, i.e. the regions whose
+// sizes solve adds up
+fn accepted_boxes(
+    toy: &BoundedToy,
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    workflow: &WorkFlow,
+) -> Vec<BoundedToy> {
+    decided_boxes(toy, workflows, workflow)
+        .into_iter()
+        .filter(|(_, res)| *res == WorkFlowResult::Accepted)
+        .map(|(toy, _)| toy)
+        .collect()
+}
+
 fn solve_rule(
     old_toy: &BoundedToy,
     workflows: &HashMap<WorkflowName, WorkFlow>,
     rule: &Rule,
     action: &Action,
-) -> u64 {
+) -> Result<u128, CountOverflow> {
     if let Some(toy) = constrain_toy(old_toy, &rule.category, &rule.comparison, rule.val) {
         match action {
             Action::Accept => {
-                println!("accepted {:?}", toy);
+                // println!("accepted {:?}", toy);
                 get_toy_options(&toy)
             }
-            Action::Reject => 0,
+            Action::Reject => Ok(0),
             Action::Switch(new_wf) => solve(&toy, workflows, workflows.get(new_wf).unwrap()),
         }
     } else {
-        0
+        Ok(0)
     }
 }
 
@@ -370,34 +576,34 @@ fn solve(
     toy: &BoundedToy,
     workflows: &HashMap<WorkflowName, WorkFlow>,
     workflow: &WorkFlow,
-) -> u64 {
+) -> Result<u128, CountOverflow> {
     let (default_toy, rules_options) =
         workflow
             .rules
             .iter()
-            .fold((Some(toy.clone()), 0), |(toy, acc), (rule, action)| {
+            .try_fold((Some(toy.clone()), 0), |(toy, acc), (rule, action)| {
                 // solve_rule(&toy, &workflows, rule, action)
                 if let Some(toy) = toy {
-                    let new_acc = acc + solve_rule(&toy, &workflows, rule, action);
+                    let new_acc = add_options(acc, solve_rule(&toy, workflows, rule, action)?)?;
                     let new_toy =
                         constrain_toy_inverse(&toy, &rule.category, &rule.comparison, rule.val);
-                    return (new_toy, new_acc);
+                    Ok((new_toy, new_acc))
                 } else {
-                    return (toy, acc);
+                    Ok((toy, acc))
                 }
-            });
+            })?;
     if let Some(toy) = default_toy {
-      let default_options = match &workflow.default {
-        Action::Accept => {
-            println!("accepted (default) {:?}", toy);
-            get_toy_options(&toy)
-        }
-        Action::Reject => 0,
-        Action::Switch(new_wf) => solve(&toy, workflows, workflows.get(new_wf).unwrap()),
-    };
-    return default_options + rules_options;
+        let default_options = match &workflow.default {
+            Action::Accept => {
+                // println!("accepted (default) {:?}", toy);
+                get_toy_options(&toy)?
+            }
+            Action::Reject => 0,
+            Action::Switch(new_wf) => solve(&toy, workflows, workflows.get(new_wf).unwrap())?,
+        };
+        add_options(default_options, rules_options)
     } else {
-      return rules_options;
+        Ok(rules_options)
     }
     // let mut rules_options = 0;
     // let mut rules_toy = Some(toy.clone());
@@ -405,29 +611,392 @@ fn solve(
     //   rules_options += solve_rule(&toy, &workflows, &rule, &action);
     //   rules_toy = constrain_toy_inverse(&toy, &rule.category, &rule.comparison, rule.val)
     // }
-    
+
     // todo!()
 }
 
-pub(crate) fn main() {
-    let input =
-        fs::read_to_string("/Users/deverkemmenash/Desktop/2023/AoC/rust/aoc/inputs/day_19.txt")
-            .unwrap();
-    let lines = input.lines();
-    let mut workflows = HashMap::<WorkflowName, WorkFlow>::new();
-    lines
-        .take_while(|line| !line.is_empty())
-        .map(|line| parse_workflow(line))
-        .for_each(|(name, wf)| {
-            // println!("Adding workflow {}: {:?}", name, wf);
-            workflows.insert(name, wf);
-        });
-    let res = solve(
-        &BoundedToy::default(),
-        &workflows,
-        workflows.get("in").unwrap(),
+// accepted combinations in `region` for parts that enter at `entry`
+fn solve_from(
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+    region: &BoundedToy,
+) -> Result<u128, SolveError> {
+    validate::validate(workflows, entry).map_err(SolveError::Invalid)?;
+    memo::Memo::new(workflows)
+        .solve(region, entry)
+        .map_err(SolveError::Overflow)
+}
+
+fn load_workflows(rules_path: &str) -> Result<HashMap<WorkflowName, WorkFlow>, String> {
+    let input = fs::read_to_string(rules_path)
+        .map_err(|err| format!("unable to read {}: {}", rules_path, err))?;
+    parse_workflows(&input).map_err(|err| format!("{}: {}", rules_path, err))
+}
+
+fn read_workflows(rules_path: &str) -> Result<HashMap<WorkflowName, WorkFlow>, String> {
+    let workflows = load_workflows(rules_path)?;
+    if !workflows.contains_key("in") {
+        return Err(format!("missing in workflow in {}", rules_path));
+    }
+    Ok(workflows)
+}
+
+// the region is a list of conditions such as x=1..=100 or m<2000, separated
+// by commas or spaces
+pub fn solve_rules(rules_path: &str, entry: &str, region: &str) -> Result<String, String> {
+    let workflows = load_workflows(rules_path)?;
+    let conditions = region
+        .split([',', ' '])
+        .filter(|condition| !condition.is_empty());
+    let region = parse_region(conditions)?;
+    match region {
+        Some(region) => solve_from(&workflows, entry, &region)
+            .map(|count| count.to_string())
+            .map_err(|err| format!("{}: {}", rules_path, err)),
+        None => Ok("0".to_string()),
+    }
+}
+
+pub fn explain_part(rules_path: &str, part: &str, json: bool) -> Result<String, String> {
+    let workflows = read_workflows(rules_path)?;
+    validate::validate(&workflows, "in").map_err(|err| err.to_string())?;
+    let toy = parse_toy(part).map_err(|err| format!("invalid part: {}", err))?;
+    let trace = trace::trace_toy(&workflows, "in", &toy).map_err(|err| err.to_string())?;
+    if json {
+        Ok(trace.to_json(&toy).to_string())
+    } else {
+        Ok(trace.to_string())
+    }
+}
+
+pub fn batch_evaluate(
+    rules_path: &str,
+    parts_path: &str,
+    format: Option<&str>,
+    output: Option<&str>,
+) -> Result<String, String> {
+    let workflows = read_workflows(rules_path)?;
+    validate::validate(&workflows, "in").map_err(|err| err.to_string())?;
+    let format = match format {
+        Some(format) => format.parse()?,
+        None => batch::Format::from_path(parts_path)
+            .ok_or(format!("can't tell the format of {}, pass --format", parts_path))?,
+    };
+    let output = output.map_or(Ok(format), |output| output.parse())?;
+    let file = fs::File::open(parts_path)
+        .map_err(|err| format!("unable to read {}: {}", parts_path, err))?;
+    let records = match format {
+        batch::Format::Csv => batch::read_csv(file)?,
+        batch::Format::JsonLines => batch::read_json_lines(BufReader::new(file)),
+    };
+    let (outcomes, errors) = batch::evaluate(&workflows, "in", records);
+    for (row, err) in &errors {
+        eprintln!("{} row {}: {}", parts_path, row, err);
+    }
+    Ok(batch::write(&outcomes, output))
+}
+
+pub fn repl(rules_path: &str) -> Result<(), String> {
+    let workflows = read_workflows(rules_path)?;
+    repl::run(workflows, io::stdin().lock(), io::stdout()).map_err(|err| err.to_string())
+}
+
+pub fn workflow_diff(old_path: &str, new_path: &str) -> Result<String, String> {
+    let old = read_workflows(old_path)?;
+    let new = read_workflows(new_path)?;
+    validate::validate(&old, "in").map_err(|err| format!("{}: {}", old_path, err))?;
+    validate::validate(&new, "in").map_err(|err| format!("{}: {}", new_path, err))?;
+    diff::render(&diff::diff(&old, &new, "in", &BoundedToy::default()))
+        .map_err(|err| err.to_string())
+}
+
+pub fn optimize_rules(rules_path: &str) -> Result<String, String> {
+    let workflows = read_workflows(rules_path)?;
+    validate::validate(&workflows, "in").map_err(|err| err.to_string())?;
+    let optimized = optimize::optimize(&workflows, "in");
+    if !optimize::equivalent(&workflows, &optimized, "in") {
+        return Err("optimized workflows accept different parts".to_string());
+    }
+    let count_rules = |workflows: &HashMap<WorkflowName, WorkFlow>| -> usize {
+        workflows.values().map(|wf| wf.rules.len()).sum()
+    };
+    eprintln!(
+        "workflows: {} -> {}, rules: {} -> {}",
+        workflows.len(),
+        optimized.len(),
+        count_rules(&workflows),
+        count_rules(&optimized)
+    );
+    Ok(print::print_workflows(&optimized))
+}
+
+fn read_valid_workflows(rules_path: &str) -> Result<HashMap<WorkflowName, WorkFlow>, String> {
+    let workflows = read_workflows(rules_path)?;
+    validate::validate(&workflows, "in").map_err(|err| format!("{}: {}", rules_path, err))?;
+    Ok(workflows)
+}
+
+// the largest x + m + a + s of any part the workflows accept; None when they
+// accept nothing
+pub fn max_accepted_sum(rules_path: &str) -> Result<Option<u128>, String> {
+    let workflows = read_valid_workflows(rules_path)?;
+    let region = BoundedToy::default();
+    Ok(query::max_accepted_sum(&workflows, "in", &region))
+}
+
+// the smallest x + m + a + s of any part the workflows accept
+pub fn min_accepted_sum(rules_path: &str) -> Result<Option<u128>, String> {
+    let workflows = read_valid_workflows(rules_path)?;
+    let region = BoundedToy::default();
+    Ok(query::min_accepted_sum(&workflows, "in", &region))
+}
+
+// x + m + a + s added up over every accepted part
+pub fn total_accepted_sum(rules_path: &str) -> Result<u128, String> {
+    let workflows = read_valid_workflows(rules_path)?;
+    let region = BoundedToy::default();
+    query::total_accepted_sum(&workflows, "in", &region).map_err(|err| err.to_string())
+}
+
+// one accepted part for each disjoint box of accepted parts
+pub fn accepted_witnesses(rules_path: &str) -> Result<Vec<Toy>, String> {
+    let workflows = read_valid_workflows(rules_path)?;
+    let region = BoundedToy::default();
+    Ok(query::accepted_witnesses(&workflows, "in", &region))
+}
+
+pub fn query_accepted(rules_path: &str, witnesses: bool) -> Result<String, String> {
+    let workflows = read_valid_workflows(rules_path)?;
+    let region = BoundedToy::default();
+    let show = |val: Option<u128>| val.map_or("none".to_string(), |val| val.to_string());
+    let mut out = format!(
+        "max rating sum: {}\nmin rating sum: {}\ntotal rating sum: {}",
+        show(query::max_accepted_sum(&workflows, "in", &region)),
+        show(query::min_accepted_sum(&workflows, "in", &region)),
+        query::total_accepted_sum(&workflows, "in", &region).map_err(|err| err.to_string())?
     );
-    println!("Options {}", res)
+    if witnesses {
+        for toy in query::accepted_witnesses(&workflows, "in", &region) {
+            out.push_str(&format!("\n{}", toy));
+        }
+    }
+    Ok(out)
+}
+
+// with a target (a workflow, `px:2` for its second rule or `px:default`),
+// the boxes of parts that reach it; without one, every branch no part takes
+pub fn reach_query(rules_path: &str, target: Option<&str>) -> Result<String, String> {
+    let workflows = read_workflows(rules_path)?;
+    validate::validate(&workflows, "in").map_err(|err| err.to_string())?;
+    let reach = reach::reach(&workflows, "in", &BoundedToy::default());
+    let Some(target) = target else {
+        let lines: Vec<String> = reach
+            .unreachable(&workflows)
+            .iter()
+            .map(|branch| {
+                let workflow = &workflows[branch.workflow];
+                if branch.is_default {
+                    format!("{} default -> {}", branch.workflow, workflow.default)
+                } else {
+                    let (rule, action) = &workflow.rules[branch.index];
+                    let number = branch.index + 1;
+                    format!(
+                        "{} rule {}: {} -> {}",
+                        branch.workflow, number, rule, action
+                    )
+                }
+            })
+            .collect();
+        if lines.is_empty() {
+            return Ok("every rule is reachable".to_string());
+        }
+        return Ok(lines.join("\n"));
+    };
+    let (name, rule) = match target.split_once(':') {
+        Some((name, rule)) => (name, Some(rule)),
+        None => (target, None),
+    };
+    let workflow = workflows
+        .get(name)
+        .ok_or(format!("unknown workflow {}", name))?;
+    let boxes = match rule {
+        None => reach.workflow(name),
+        Some("default") => reach.branch(name, workflow.rules.len()),
+        Some(rule) => {
+            let number: usize = rule
+                .parse()
+                .map_err(|_| format!("invalid rule number {}", rule))?;
+            if number == 0 || number > workflow.rules.len() {
+                return Err(format!("{} has no rule {}", name, number));
+            }
+            reach.branch(name, number - 1)
+        }
+    };
+    let mut out = format!(
+        "{} parts in {} boxes",
+        total_options(boxes).map_err(|err| err.to_string())?,
+        boxes.len()
+    );
+    for toy in boxes {
+        out.push_str(&format!("\n{}", toy));
+    }
+    Ok(out)
+}
+
+pub fn generate_input(
+    seed: u64,
+    depth: usize,
+    fan_out: usize,
+    parts: usize,
+    fault: Option<&str>,
+) -> Result<String, String> {
+    let fault = fault.map(|fault| fault.parse()).transpose()?;
+    let config = generate::GenConfig {
+        depth,
+        fan_out,
+        parts,
+        ..Default::default()
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (workflows, toys) = generate::generate(&mut rng, &config);
+    Ok(generate::render(&mut rng, &workflows, &toys, fault))
+}
+
+pub fn fuzz(seed: u64, iterations: u64) -> Result<String, String> {
+    fuzz::run(seed, iterations)
+}
+
+// spread parts evenly over the 1..=4000 domain without needing a rng
+fn sample_toys(count: u64) -> Vec<Toy> {
+    (0..count)
+        .map(|i| Toy {
+            x: 1 + (i * 7919) % 4000,
+            m: 1 + (i * 104729) % 4000,
+            a: 1 + (i * 1299709) % 4000,
+            s: 1 + (i * 15485863) % 4000,
+        })
+        .collect()
+}
+
+pub fn bench_compiled(rules_path: &str, count: u64) -> Result<String, String> {
+    let workflows = read_workflows(rules_path)?;
+    let program = compile::compile(&workflows, "in")?;
+    let toys = sample_toys(count);
+
+    let start = Instant::now();
+    let interpreted: Vec<WorkFlowResult> = toys
+        .iter()
+        .map(|toy| handle_workflow(&workflows, &workflows["in"], toy))
+        .collect();
+    let interpreted_time = start.elapsed();
+
+    let start = Instant::now();
+    let compiled = program.evaluate_all(&toys);
+    let compiled_time = start.elapsed();
+
+    if interpreted != compiled {
+        return Err("compiled program disagrees with handle_workflow".to_string());
+    }
+    Ok(format!(
+        "{} parts\ninterpreted: {:?}\ncompiled: {:?}",
+        count, interpreted_time, compiled_time
+    ))
+}
+
+// the plain recursion against the memoized walk on a generated chain of
+// diamonds; the recursion walks the whole tail again for every region that
+// reaches the bottom of the diamonds, the memoized walk only once
+pub fn bench_solve(seed: u64, depth: usize, tail: usize) -> Result<String, String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let workflows = generate::diamond(&mut rng, depth, tail);
+    let region = BoundedToy::default();
+
+    let start = Instant::now();
+    let recursive = solve(&region, &workflows, &workflows["in"]).map_err(|err| err.to_string())?;
+    let recursive_time = start.elapsed();
+
+    let start = Instant::now();
+    let memoized = memo::Memo::new(&workflows)
+        .solve(&region, "in")
+        .map_err(|err| err.to_string())?;
+    let memoized_time = start.elapsed();
+
+    if recursive != memoized {
+        return Err(format!(
+            "memoized solve counted {} but the recursion counted {}",
+            memoized, recursive
+        ));
+    }
+    Ok(format!(
+        "{} workflows, depth {}, tail {}, {} accepted\nrecursive: {:?}\nmemoized: {:?}",
+        workflows.len(),
+        depth,
+        tail,
+        recursive,
+        recursive_time,
+        memoized_time
+    ))
+}
+
+// the puzzle input read a line at a time: the workflows are kept, but each
+// part is evaluated and dropped as soon as it's read, so only the workflows
+// need to fit in memory; unlike parse_input, every workflow and part has to
+// be on a line of its own. `path` can be `-` for stdin
+pub fn solve_input(path: &str) -> Result<String, String> {
+    let name = input::name(path);
+    let (accepted_sum, combinations) =
+        solve_lines(input::open(path)?).map_err(|err| format!("{}: {}", name, err))?;
+    Ok(format!(
+        "accepted rating sum: {}\ncombinations: {}",
+        accepted_sum, combinations
+    ))
+}
+
+// the rating sum of the accepted parts and the number of accepted combinations
+fn solve_lines(reader: impl BufRead) -> Result<(u128, u128), String> {
+    let mut workflows = HashMap::new();
+    let mut program = None;
+    let mut accepted_sum: u128 = 0;
+    for line in input::lines(reader) {
+        let (number, line) = line?;
+        // the untrimmed line is parsed, so columns count its indentation
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed.starts_with('{') {
+            if program.is_none() {
+                program = Some(compile::compile(&workflows, "in")?);
+            }
+            let toy = parse_toy(&line).map_err(|err| err.on_line(number).to_string())?;
+            if program.as_ref().unwrap().evaluate(&toy) == WorkFlowResult::Accepted {
+                accepted_sum += sum(&toy);
+            }
+        } else {
+            if program.is_some() {
+                return Err(format!("line {}: workflow after the parts", number));
+            }
+            let (workflow_name, workflow) =
+                parse_workflow(&line).map_err(|err| err.on_line(number).to_string())?;
+            if workflows.contains_key(&workflow_name) {
+                return Err(format!(
+                    "line {}: duplicate workflow `{}`",
+                    number, workflow_name
+                ));
+            }
+            workflows.insert(workflow_name, workflow);
+        }
+    }
+    let combinations =
+        solve_from(&workflows, "in", &BoundedToy::default()).map_err(|err| err.to_string())?;
+    Ok((accepted_sum, combinations))
+}
+
+pub fn main() {
+    match solve_input("/Users/deverkemmenash/Desktop/2023/AoC/rust/aoc/inputs/day_19.txt") {
+        Ok(res) => println!("{}", res),
+        Err(err) => println!("{}", err),
+    }
     // let total: u64 = lines
     //     .skip_while(|line| !line.is_empty())
     //     .skip(1)
@@ -0,0 +1,319 @@
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Read},
+    str::FromStr,
+};
+
+use super::{sum, trace, Toy, WorkFlow, WorkFlowResult, WorkflowName};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Format {
+    Csv,
+    JsonLines,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "csv" => Ok(Format::Csv),
+            "jsonl" | "json" => Ok(Format::JsonLines),
+            _ => Err(format!("unknown format {}, expected csv or jsonl", format)),
+        }
+    }
+}
+
+impl Format {
+    pub(super) fn from_path(path: &str) -> Option<Format> {
+        let extension = path.rsplit_once('.')?.1;
+        extension.to_ascii_lowercase().parse().ok()
+    }
+}
+
+// one row of the input, numbered as it appears in the file (the csv header
+// and blank json lines count), with the part or why it couldn't be read
+#[derive(Debug)]
+pub(super) struct Record {
+    row: usize,
+    toy: Result<Toy, String>,
+}
+
+const CATEGORIES: [&str; 4] = ["x", "m", "a", "s"];
+
+fn toy_from_ratings(ratings: [u64; 4]) -> Toy {
+    let [x, m, a, s] = ratings;
+    Toy { x, m, a, s }
+}
+
+fn csv_toy(record: &csv::StringRecord, columns: &[usize; 4]) -> Result<Toy, String> {
+    let mut ratings = [0; 4];
+    for (i, column) in columns.iter().enumerate() {
+        let field = record
+            .get(*column)
+            .ok_or(format!("missing {} value", CATEGORIES[i]))?
+            .trim();
+        ratings[i] = field
+            .parse()
+            .map_err(|_| format!("invalid {} value `{}`", CATEGORIES[i], field))?;
+    }
+    Ok(toy_from_ratings(ratings))
+}
+
+// a first row naming any of x, m, a and s is a header, and then has to name
+// all four (in any order, next to any other columns); otherwise the first four
+// columns are x, m, a, s and the first row is a part like any other
+pub(super) fn read_csv(reader: impl Read) -> Result<Vec<Record>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+    let mut records = reader.records().peekable();
+    let mut columns = [0, 1, 2, 3];
+    let mut header_rows = 0;
+    if let Some(Ok(first)) = records.peek() {
+        let names: Vec<String> = first
+            .iter()
+            .map(|field| field.trim().trim_start_matches('\u{feff}').to_lowercase())
+            .collect();
+        if names.iter().any(|name| CATEGORIES.contains(&name.as_str())) {
+            for (i, category) in CATEGORIES.iter().enumerate() {
+                columns[i] = names
+                    .iter()
+                    .position(|name| name == category)
+                    .ok_or(format!("csv header has no {} column", category))?;
+            }
+            records.next();
+            header_rows = 1;
+        }
+    }
+    Ok(records
+        .enumerate()
+        .map(|(i, record)| {
+            let position = match &record {
+                Ok(record) => record.position(),
+                Err(err) => err.position(),
+            };
+            let row = position.map_or(i + 1 + header_rows, |position| position.line() as usize);
+            let toy = record
+                .map_err(|err| err.to_string())
+                .and_then(|record| csv_toy(&record, &columns));
+            Record { row, toy }
+        })
+        .collect())
+}
+
+fn json_toy(line: &str) -> Result<Toy, String> {
+    let value: Value = serde_json::from_str(line).map_err(|err| err.to_string())?;
+    let mut ratings = [0; 4];
+    for (i, category) in CATEGORIES.iter().enumerate() {
+        let field = value
+            .get(category)
+            .ok_or(format!("missing {} value", category))?;
+        ratings[i] = field
+            .as_u64()
+            .or_else(|| field.as_str().and_then(|field| field.trim().parse().ok()))
+            .ok_or(format!("invalid {} value {}", category, field))?;
+    }
+    Ok(toy_from_ratings(ratings))
+}
+
+// one object with x, m, a and s per line; blank lines are skipped
+pub(super) fn read_json_lines(reader: impl BufRead) -> Vec<Record> {
+    reader
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let toy = match line {
+                Ok(line) if line.trim().is_empty() => return None,
+                Ok(line) => json_toy(&line),
+                Err(err) => Err(err.to_string()),
+            };
+            Some(Record { row: i + 1, toy })
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub(super) struct Outcome<'a> {
+    row: usize,
+    toy: Toy,
+    result: WorkFlowResult,
+    // the workflow whose rule made the decision
+    workflow: &'a str,
+    sum: u128,
+}
+
+// evaluates every readable record and hands back the errors for the rest
+pub(super) fn evaluate<'a>(
+    workflows: &'a HashMap<WorkflowName, WorkFlow>,
+    entry: &'a str,
+    records: Vec<Record>,
+) -> (Vec<Outcome<'a>>, Vec<(usize, String)>) {
+    let mut outcomes = Vec::new();
+    let mut errors = Vec::new();
+    for Record { row, toy } in records {
+        match toy {
+            Ok(toy) => match trace::trace_toy(workflows, entry, &toy) {
+                Ok(trace) => {
+                    let sum = sum(&toy);
+                    outcomes.push(Outcome {
+                        row,
+                        result: trace.result(),
+                        workflow: trace.final_workflow(),
+                        toy,
+                        sum,
+                    });
+                }
+                Err(err) => errors.push((row, err.to_string())),
+            },
+            Err(err) => errors.push((row, err)),
+        }
+    }
+    (outcomes, errors)
+}
+
+pub(super) fn write(outcomes: &[Outcome], format: Format) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Csv => {
+            out.push_str("row,x,m,a,s,result,workflow,sum\n");
+            for outcome in outcomes {
+                let Outcome {
+                    row,
+                    toy,
+                    result,
+                    workflow,
+                    sum,
+                } = outcome;
+                out.push_str(&format!(
+                    "{},{},{},{},{},{},{},{}\n",
+                    row, toy.x, toy.m, toy.a, toy.s, result, workflow, sum
+                ));
+            }
+        }
+        Format::JsonLines => {
+            for outcome in outcomes {
+                let line = json!({
+                    "row": outcome.row,
+                    "x": outcome.toy.x,
+                    "m": outcome.toy.m,
+                    "a": outcome.toy.a,
+                    "s": outcome.toy.s,
+                    "result": outcome.result.to_string(),
+                    "workflow": outcome.workflow,
+                    // only falls back to a string past u64, which json
+                    // numbers can't hold
+                    "sum": u64::try_from(outcome.sum)
+                        .map_or_else(|_| json!(outcome.sum.to_string()), |sum| json!(sum)),
+                });
+                out.push_str(&format!("{}\n", line));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, read_csv, read_json_lines, write, Format, Record};
+    use crate::day19::{tests::example, Toy};
+
+    fn toy(x: u64, m: u64, a: u64, s: u64) -> Toy {
+        Toy { x, m, a, s }
+    }
+
+    fn rows(records: Vec<Record>) -> Vec<(usize, Result<Toy, String>)> {
+        records
+            .into_iter()
+            .map(|record| (record.row, record.toy))
+            .collect()
+    }
+
+    fn csv(input: &str) -> Vec<(usize, Result<Toy, String>)> {
+        rows(read_csv(input.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn csv_header_picks_the_columns() {
+        assert_eq!(
+            csv("id,s,a,m,x,note\n1,4,3,2,1,first\n2,8,7,6,5,second\n"),
+            vec![(2, Ok(toy(1, 2, 3, 4))), (3, Ok(toy(5, 6, 7, 8)))]
+        );
+        assert_eq!(
+            csv("\u{feff}X, M ,a,s\n1,2,3,4\n"),
+            vec![(2, Ok(toy(1, 2, 3, 4)))]
+        );
+        assert_eq!(
+            read_csv("x,m,a,note\n1,2,3,4\n".as_bytes()).unwrap_err(),
+            "csv header has no s column"
+        );
+    }
+
+    #[test]
+    fn csv_without_a_header() {
+        assert_eq!(
+            csv("1,2,3,4\n5,6,7,8,extra\n"),
+            vec![(1, Ok(toy(1, 2, 3, 4))), (2, Ok(toy(5, 6, 7, 8)))]
+        );
+    }
+
+    #[test]
+    fn bad_csv_rows() {
+        assert_eq!(
+            csv("x,m,a,s\n1,2,3\n1,two,3,4\n5,6,7,8\n"),
+            vec![
+                (2, Err("missing s value".to_string())),
+                (3, Err("invalid m value `two`".to_string())),
+                (4, Ok(toy(5, 6, 7, 8))),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_lines() {
+        let input = r#"{"x":1,"m":2,"a":3,"s":4}
+
+{"x":1,"m":2,
+{"x":"5","m":" 6 ","a":7,"s":8}
+{"x":-1,"m":2,"a":3,"s":4}
+{"x":1,"m":2,"a":3}
+"#;
+        let records = rows(read_json_lines(input.as_bytes()));
+        let rows: Vec<usize> = records.iter().map(|(row, _)| *row).collect();
+        assert_eq!(rows, vec![1, 3, 4, 5, 6]);
+        assert_eq!(records[0].1, Ok(toy(1, 2, 3, 4)));
+        assert!(records[1].1.is_err());
+        assert_eq!(records[2].1, Ok(toy(5, 6, 7, 8)));
+        assert_eq!(records[3].1, Err("invalid x value -1".to_string()));
+        assert_eq!(records[4].1, Err("missing s value".to_string()));
+    }
+
+    // rows keep their numbers in the file through evaluation, header included
+    #[test]
+    fn evaluate_reports_file_rows() {
+        let workflows = example();
+        let input = "x,m,a,s
+787,2655,1222,2876
+1679,44,2067,496
+2036,264,79,oops
+2461,1339,466,291
+";
+        let records = read_csv(input.as_bytes()).unwrap();
+        let (outcomes, errors) = evaluate(&workflows, "in", records);
+        assert_eq!(errors, vec![(4, "invalid s value `oops`".to_string())]);
+        assert_eq!(
+            write(&outcomes, Format::Csv),
+            "row,x,m,a,s,result,workflow,sum
+2,787,2655,1222,2876,Accepted,lnx,7540
+3,1679,44,2067,496,Rejected,gd,4286
+5,2461,1339,466,291,Rejected,crn,4557
+"
+        );
+        assert_eq!(
+            write(&outcomes[..1], Format::JsonLines),
+            "{\"a\":1222,\"m\":2655,\"result\":\"Accepted\",\"row\":2,\"s\":2876,\"sum\":7540,\"workflow\":\"lnx\",\"x\":787}\n"
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use super::{validate, Action, Category, Comparison, Toy, WorkFlow, WorkFlowResult, WorkflowName};
+
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    Accept,
+    Reject,
+    Jump(u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FlatRule {
+    category: usize,
+    less_than: bool,
+    val: u64,
+    target: Target,
+}
+
+// workflows flattened into one rule array; the rules of workflow `i` are
+// `rules[starts[i]..starts[i + 1]]` and workflows refer to each other by index
+#[derive(Debug)]
+pub(super) struct Program {
+    starts: Vec<usize>,
+    rules: Vec<FlatRule>,
+    defaults: Vec<Target>,
+    entry: u32,
+}
+
+fn category_index(category: &Category) -> usize {
+    match category {
+        Category::X => 0,
+        Category::M => 1,
+        Category::A => 2,
+        Category::S => 3,
+    }
+}
+
+fn target(ids: &HashMap<&str, u32>, action: &Action) -> Result<Target, String> {
+    match action {
+        Action::Accept => Ok(Target::Accept),
+        Action::Reject => Ok(Target::Reject),
+        Action::Switch(name) => ids
+            .get(name.as_str())
+            .map(|id| Target::Jump(*id))
+            .ok_or(format!("unknown workflow {}", name)),
+    }
+}
+
+// the workflows are validated first: evaluate follows jumps until it hits an
+// accept or reject, so a cycle would never return
+pub(super) fn compile(
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+) -> Result<Program, String> {
+    validate::validate(workflows, entry).map_err(|err| err.to_string())?;
+    let mut names: Vec<WorkflowName> = workflows.keys().cloned().collect();
+    names.sort();
+    let ids: HashMap<&str, u32> = names
+        .iter()
+        .enumerate()
+        .map(|(id, name)| (name.as_str(), id as u32))
+        .collect();
+    let entry = *ids
+        .get(entry)
+        .ok_or(format!("unknown workflow {}", entry))?;
+    let mut starts = vec![0];
+    let mut rules = Vec::new();
+    let mut defaults = Vec::new();
+    for name in &names {
+        let workflow = &workflows[name];
+        for (rule, action) in &workflow.rules {
+            rules.push(FlatRule {
+                category: category_index(&rule.category),
+                less_than: matches!(rule.comparison, Comparison::LessThan),
+                val: rule.val,
+                target: target(&ids, action)?,
+            });
+        }
+        starts.push(rules.len());
+        defaults.push(target(&ids, &workflow.default)?);
+    }
+    Ok(Program {
+        starts,
+        rules,
+        defaults,
+        entry,
+    })
+}
+
+impl Program {
+    pub(super) fn evaluate(&self, toy: &Toy) -> WorkFlowResult {
+        let ratings = [toy.x, toy.m, toy.a, toy.s];
+        let mut id = self.entry as usize;
+        loop {
+            let rules = &self.rules[self.starts[id]..self.starts[id + 1]];
+            let target = rules
+                .iter()
+                .find(|rule| {
+                    let rating = ratings[rule.category];
+                    if rule.less_than {
+                        rating < rule.val
+                    } else {
+                        rating > rule.val
+                    }
+                })
+                .map_or(self.defaults[id], |rule| rule.target);
+            match target {
+                Target::Accept => return WorkFlowResult::Accepted,
+                Target::Reject => return WorkFlowResult::Rejected,
+                Target::Jump(next) => id = next as usize,
+            }
+        }
+    }
+
+    pub(super) fn evaluate_all(&self, toys: &[Toy]) -> Vec<WorkFlowResult> {
+        toys.iter().map(|toy| self.evaluate(toy)).collect()
+    }
+}
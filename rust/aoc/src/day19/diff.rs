@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use super::{
+    decided_boxes, get_toy_options, min_toy, total_options, BoundedToy, CountOverflow, WorkFlow,
+    WorkFlowResult, WorkflowName,
+};
+
+// a region where the two workflow sets disagree; the witness is its smallest
+// corner, and the new set does the opposite of `old` there
+#[derive(Debug)]
+pub(super) struct Difference {
+    region: BoundedToy,
+    old: WorkFlowResult,
+}
+
+// every box of `region` the old set decides on is split again by the new set,
+// so each resulting piece has a single result under both
+pub(super) fn diff(
+    old: &HashMap<WorkflowName, WorkFlow>,
+    new: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+    region: &BoundedToy,
+) -> Vec<Difference> {
+    decided_boxes(region, old, &old[entry])
+        .into_iter()
+        .flat_map(|(region, old_res)| {
+            decided_boxes(&region, new, &new[entry])
+                .into_iter()
+                .filter(move |(_, new_res)| *new_res != old_res)
+                .map(move |(region, _)| Difference {
+                    region,
+                    old: old_res,
+                })
+        })
+        .collect()
+}
+
+fn render_section(
+    out: &mut String,
+    title: &str,
+    differences: &[&Difference],
+) -> Result<(), CountOverflow> {
+    if differences.is_empty() {
+        return Ok(());
+    }
+    let total = total_options(differences.iter().map(|difference| &difference.region))?;
+    out.push_str(&format!("{} ({} parts):\n", title, total));
+    for difference in differences {
+        out.push_str(&format!(
+            "  {} ({} parts) e.g. {}\n",
+            difference.region,
+            get_toy_options(&difference.region)?,
+            min_toy(&difference.region)
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn render(differences: &[Difference]) -> Result<String, CountOverflow> {
+    if differences.is_empty() {
+        return Ok("no differences".to_string());
+    }
+    let (now_rejected, now_accepted): (Vec<&Difference>, Vec<&Difference>) = differences
+        .iter()
+        .partition(|difference| difference.old == WorkFlowResult::Accepted);
+    let mut out = String::new();
+    render_section(&mut out, "accepted by old, rejected by new", &now_rejected)?;
+    render_section(&mut out, "rejected by old, accepted by new", &now_accepted)?;
+    Ok(out.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, render};
+    use crate::day19::{
+        get_toy_options, min_toy, parse_region, parse_workflows, tests::example, BoundedToy, Toy,
+        WorkFlowResult,
+    };
+
+    #[test]
+    fn finds_where_the_sets_disagree() {
+        let old = parse_workflows("in{x<5:A,A}").unwrap();
+        let new = parse_workflows("in{x<6:A,R}").unwrap();
+        let differences = diff(&old, &new, "in", &BoundedToy::default());
+        assert_eq!(differences.len(), 1);
+        let difference = &differences[0];
+        assert_eq!(difference.old, WorkFlowResult::Accepted);
+        assert_eq!(
+            difference.region.to_string(),
+            "x=6..=4000 m=1..=4000 a=1..=4000 s=1..=4000"
+        );
+        assert_eq!(
+            get_toy_options(&difference.region),
+            Ok(3995 * 4000u128.pow(3))
+        );
+        assert_eq!(
+            min_toy(&difference.region),
+            Toy {
+                x: 6,
+                m: 1,
+                a: 1,
+                s: 1
+            }
+        );
+        // only the direction that has differences gets a section
+        assert_eq!(
+            render(&differences).unwrap(),
+            "accepted by old, rejected by new (255680000000000 parts):
+  x=6..=4000 m=1..=4000 a=1..=4000 s=1..=4000 (255680000000000 parts) e.g. {x=6,m=1,a=1,s=1}"
+        );
+        // the printed box reads back as a --region
+        let printed = difference.region.to_string();
+        assert_eq!(
+            parse_region(printed.split(' ')),
+            Ok(Some(difference.region.clone()))
+        );
+
+        let back = diff(&new, &old, "in", &BoundedToy::default());
+        assert_eq!(back.len(), 1);
+        assert_eq!(back[0].old, WorkFlowResult::Rejected);
+        assert_eq!(back[0].region, difference.region);
+    }
+
+    #[test]
+    fn identical_sets_have_no_differences() {
+        let workflows = example();
+        let differences = diff(&workflows, &workflows, "in", &BoundedToy::default());
+        assert!(differences.is_empty());
+        assert_eq!(render(&differences).unwrap(), "no differences");
+    }
+}
@@ -0,0 +1,106 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::panic;
+
+use super::{
+    compile,
+    generate::{generate, render, Fault, GenConfig},
+    handle_workflow, optimize, parse, solve, trace,
+    validate::{validate, ValidationError},
+    BoundedToy,
+};
+
+// one fuzz case: a randomly shaped input, sometimes broken on purpose, pushed
+// through the parser, validator and every evaluator
+fn fuzz_one(seed: u64) -> Result<(), String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let config = GenConfig {
+        depth: rng.random_range(0..7),
+        fan_out: rng.random_range(1..7),
+        domain_max: if rng.random() {
+            4000
+        } else {
+            rng.random_range(1..30)
+        },
+        parts: rng.random_range(0..50),
+    };
+    let (workflows, toys) = generate(&mut rng, &config);
+    let fault = match rng.random_range(0..6) {
+        0 => Some(Fault::DanglingReference),
+        1 => Some(Fault::Cycle),
+        2 => Some(Fault::MalformedToken),
+        _ => None,
+    };
+    let input = render(&mut rng, &workflows, &toys, fault);
+
+    let parsed = match (parse::parse_input(&input), fault) {
+        (Err(_), Some(Fault::MalformedToken)) => return Ok(()),
+        (Ok(_), Some(Fault::MalformedToken)) => return Err("malformed input parsed".to_string()),
+        (Err(err), _) => return Err(format!("valid input failed to parse: {}", err)),
+        (Ok(parsed), _) => parsed,
+    };
+    match (validate(&parsed.workflows, "in"), fault) {
+        (Err(ValidationError::DanglingReference { .. }), Some(Fault::DanglingReference)) => {
+            return Ok(())
+        }
+        (Err(ValidationError::Cycle(_)), Some(Fault::Cycle)) => return Ok(()),
+        (Ok(()), None) => {}
+        (res, _) => {
+            return Err(format!(
+                "expected {:?} from validation, got {:?}",
+                fault, res
+            ))
+        }
+    }
+
+    let workflows = parsed.workflows;
+    let program = compile::compile(&workflows, "in")?;
+    for toy in &parsed.toys {
+        let res = handle_workflow(&workflows, &workflows["in"], toy);
+        let traced = trace::trace_toy(&workflows, "in", toy)
+            .map_err(|err| err.to_string())?
+            .result();
+        if program.evaluate(toy) != res || traced != res {
+            return Err(format!("evaluators disagree on {}", toy));
+        }
+    }
+    let optimized = optimize::optimize(&workflows, "in");
+    if !optimize::equivalent(&workflows, &optimized, "in") {
+        return Err("optimized workflows are not equivalent".to_string());
+    }
+    solve(&BoundedToy::default(), &workflows, &workflows["in"]).map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+// runs cases until one fails or panics (forever when `iterations` is 0); the
+// failing seed reproduces it with `--seed`
+pub(super) fn run(seed: u64, iterations: u64) -> Result<String, String> {
+    let mut i = 0;
+    while iterations == 0 || i < iterations {
+        let case = seed + i;
+        match panic::catch_unwind(|| fuzz_one(case)) {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => return Err(format!("seed {}: {}", case, err)),
+            Err(_) => return Err(format!("seed {}: panicked", case)),
+        }
+        i += 1;
+        if i % 10_000 == 0 {
+            eprintln!("{} cases", i);
+        }
+    }
+    Ok(format!("{} cases passed", iterations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fuzz_one;
+
+    // the same cases the fuzz-day19 binary starts with, as a property test
+    #[test]
+    fn generated_cases() {
+        for seed in 0..500 {
+            if let Err(err) = fuzz_one(seed) {
+                panic!("seed {}: {}", seed, err);
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use rand::{rngs::StdRng, Rng};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use super::{print, Action, Category, Comparison, Rule, Toy, WorkFlow, WorkflowName};
+
+const NAME_START: &[u8] = b"abcdefghijklmnopqrstuvwxyzQ_";
+const NAME_REST: &[u8] = b"abcdefghijklmnopqrstuvwxyzAR019_";
+
+pub(super) fn random_name(rng: &mut StdRng) -> WorkflowName {
+    let len = rng.random_range(1..=4);
+    (0..len)
+        .map(|i| {
+            let chars = if i == 0 { NAME_START } else { NAME_REST };
+            chars[rng.random_range(0..chars.len())] as char
+        })
+        .collect()
+}
+
+// values go one past 1..=max on both sides so the edges get exercised
+pub(super) fn random_rule(rng: &mut StdRng, max: u64) -> Rule {
+    let category = match rng.random_range(0..4) {
+        0 => Category::X,
+        1 => Category::M,
+        2 => Category::A,
+        _ => Category::S,
+    };
+    let comparison = if rng.random() {
+        Comparison::LessThan
+    } else {
+        Comparison::GreaterThan
+    };
+    Rule {
+        category,
+        comparison,
+        val: rng.random_range(0..=max + 1),
+    }
+}
+
+pub(super) fn random_toy(rng: &mut StdRng, max: u64) -> Toy {
+    Toy {
+        x: rng.random_range(1..=max),
+        m: rng.random_range(1..=max),
+        a: rng.random_range(1..=max),
+        s: rng.random_range(1..=max),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct GenConfig {
+    // the longest chain of workflows below `in`
+    pub(super) depth: usize,
+    // the most branches (rules plus the default) a workflow can have
+    pub(super) fan_out: usize,
+    // ratings and rule values stay around 1..=domain_max
+    pub(super) domain_max: u64,
+    pub(super) parts: usize,
+}
+
+impl Default for GenConfig {
+    fn default() -> Self {
+        GenConfig {
+            depth: 4,
+            fan_out: 4,
+            domain_max: 4000,
+            parts: 200,
+        }
+    }
+}
+
+// builds the workflows one level at a time; a workflow only ever jumps to the
+// next level, either to a brand new workflow or one a sibling already uses, so
+// the result is acyclic and every workflow is referenced from `in`
+pub(super) fn generate(
+    rng: &mut StdRng,
+    config: &GenConfig,
+) -> (HashMap<WorkflowName, WorkFlow>, Vec<Toy>) {
+    let mut used = HashSet::from(["in".to_string()]);
+    let mut levels: Vec<Vec<WorkflowName>> = vec![vec!["in".to_string()]];
+    let mut workflows = HashMap::new();
+    for depth in 0..=config.depth {
+        levels.push(Vec::new());
+        let mut i = 0;
+        while i < levels[depth].len() {
+            let mut branch = |rng: &mut StdRng, levels: &mut Vec<Vec<WorkflowName>>| {
+                if depth == config.depth || rng.random_bool(0.4) {
+                    return if rng.random() {
+                        Action::Accept
+                    } else {
+                        Action::Reject
+                    };
+                }
+                let next = &mut levels[depth + 1];
+                if !next.is_empty() && rng.random_bool(0.3) {
+                    return Action::Switch(next[rng.random_range(0..next.len())].clone());
+                }
+                let mut name = random_name(rng);
+                while used.contains(&name) {
+                    name = random_name(rng);
+                }
+                used.insert(name.clone());
+                next.push(name.clone());
+                Action::Switch(name)
+            };
+            let rules = (0..rng.random_range(0..config.fan_out.max(1)))
+                .map(|_| {
+                    let rule = random_rule(rng, config.domain_max);
+                    (rule, branch(rng, &mut levels))
+                })
+                .collect();
+            let default = branch(rng, &mut levels);
+            workflows.insert(levels[depth][i].clone(), WorkFlow { rules, default });
+            i += 1;
+        }
+    }
+    let toys = (0..config.parts)
+        .map(|_| random_toy(rng, config.domain_max))
+        .collect();
+    (workflows, toys)
+}
+
+// a chain of diamonds over a shared tail: every level splits on one category
+// into two workflows that both jump to the next level, and the last level
+// runs into a chain of `tail` workflows that each reject a sliver of parts
+//
+// in{x<1234:l0,r0}
+// l0{d1}
+// r0{d1}
+// d1{m<2345:l1,r1}
+// ...
+// d3{t0}
+// t0{s<3:R,t1}
+// ...
+// t9{A}
+pub(super) fn diamond(
+    rng: &mut StdRng,
+    depth: usize,
+    tail: usize,
+) -> HashMap<WorkflowName, WorkFlow> {
+    let level_name = |level: usize| {
+        if level == 0 {
+            "in".to_string()
+        } else {
+            format!("d{}", level)
+        }
+    };
+    let categories = [Category::X, Category::M, Category::A, Category::S];
+    let mut workflows = HashMap::new();
+    for level in 0..depth {
+        let rule = Rule {
+            category: categories[level % 4].clone(),
+            comparison: Comparison::LessThan,
+            val: rng.random_range(2..=4000),
+        };
+        let next = WorkFlow {
+            rules: Vec::new(),
+            default: Action::Switch(level_name(level + 1)),
+        };
+        workflows.insert(
+            level_name(level),
+            WorkFlow {
+                rules: vec![(rule, Action::Switch(format!("l{}", level)))],
+                default: Action::Switch(format!("r{}", level)),
+            },
+        );
+        workflows.insert(format!("l{}", level), next.clone());
+        workflows.insert(format!("r{}", level), next);
+    }
+    workflows.insert(
+        level_name(depth),
+        WorkFlow {
+            rules: Vec::new(),
+            default: Action::Switch("t0".to_string()),
+        },
+    );
+    for i in 0..tail {
+        let rule = Rule {
+            category: Category::S,
+            comparison: Comparison::LessThan,
+            val: rng.random_range(1..=10),
+        };
+        workflows.insert(
+            format!("t{}", i),
+            WorkFlow {
+                rules: vec![(rule, Action::Reject)],
+                default: Action::Switch(format!("t{}", i + 1)),
+            },
+        );
+    }
+    workflows.insert(
+        format!("t{}", tail),
+        WorkFlow {
+            rules: Vec::new(),
+            default: Action::Accept,
+        },
+    );
+    workflows
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Fault {
+    DanglingReference,
+    Cycle,
+    MalformedToken,
+}
+
+impl FromStr for Fault {
+    type Err = String;
+
+    fn from_str(fault: &str) -> Result<Self, Self::Err> {
+        match fault {
+            "dangling" => Ok(Fault::DanglingReference),
+            "cycle" => Ok(Fault::Cycle),
+            "malformed" => Ok(Fault::MalformedToken),
+            _ => Err(format!(
+                "unknown fault {}, expected dangling, cycle or malformed",
+                fault
+            )),
+        }
+    }
+}
+
+// picks a workflow by sorted name, so the choice only depends on the rng
+fn pick_workflow(rng: &mut StdRng, workflows: &HashMap<WorkflowName, WorkFlow>) -> WorkflowName {
+    let mut names: Vec<&WorkflowName> = workflows.keys().collect();
+    names.sort();
+    names[rng.random_range(0..names.len())].clone()
+}
+
+// the puzzle input for a generated set, optionally broken in one place
+pub(super) fn render(
+    rng: &mut StdRng,
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    toys: &[Toy],
+    fault: Option<Fault>,
+) -> String {
+    let mut workflows = workflows.clone();
+    match fault {
+        Some(Fault::DanglingReference) => {
+            let name = pick_workflow(rng, &workflows);
+            let mut missing = random_name(rng);
+            while workflows.contains_key(&missing) {
+                missing = random_name(rng);
+            }
+            workflows.get_mut(&name).unwrap().default = Action::Switch(missing);
+        }
+        Some(Fault::Cycle) => {
+            // everything is reachable from `in`, so pointing back at it closes
+            // a loop
+            let name = pick_workflow(rng, &workflows);
+            workflows.get_mut(&name).unwrap().default = Action::Switch("in".to_string());
+        }
+        Some(Fault::MalformedToken) => {
+            let input = print::print_input(&workflows, toys);
+            let malformation = MALFORMATIONS[rng.random_range(0..MALFORMATIONS.len())];
+            return malform(rng, &input, malformation);
+        }
+        None => {}
+    }
+    print::print_input(&workflows, toys)
+}
+
+// the ways a malformed token can break an input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Malformation {
+    // `@`, `!`, `$`, `;` or `%` in place of any character
+    StrayCharacter,
+    // `x<:A`
+    MissingValue,
+    // `px{a<2006:qkq,rfg`
+    MissingBrace,
+    // `px{a<2006:qkq}`
+    MissingDefault,
+    // `px{A,a<2006:qkq,rfg}`
+    BareAction,
+}
+
+pub(super) const MALFORMATIONS: [Malformation; 5] = [
+    Malformation::StrayCharacter,
+    Malformation::MissingValue,
+    Malformation::MissingBrace,
+    Malformation::MissingDefault,
+    Malformation::BareAction,
+];
+
+// breaks one workflow line of a printed input; a missing value needs a rule,
+// so without any it falls back to a stray character
+pub(super) fn malform(rng: &mut StdRng, input: &str, malformation: Malformation) -> String {
+    let mut lines: Vec<String> = input.lines().map(|line| line.to_string()).collect();
+    let workflows = lines.iter().take_while(|line| !line.is_empty()).count();
+    let with_rules: Vec<usize> = (0..workflows).filter(|i| lines[*i].contains(':')).collect();
+    let malformation = match malformation {
+        Malformation::MissingValue if with_rules.is_empty() => Malformation::StrayCharacter,
+        malformation => malformation,
+    };
+    let index = match malformation {
+        Malformation::MissingValue => with_rules[rng.random_range(0..with_rules.len())],
+        _ => rng.random_range(0..workflows),
+    };
+    let line = &lines[index];
+    let open = line.find('{').unwrap();
+    let name = &line[..open];
+    let mut entries: Vec<&str> = line[open + 1..line.len() - 1].split(',').collect();
+    let broken = match malformation {
+        Malformation::StrayCharacter => {
+            let mut input = input.to_string();
+            let positions: Vec<usize> = input
+                .char_indices()
+                .filter(|(_, c)| !c.is_whitespace())
+                .map(|(i, _)| i)
+                .collect();
+            let position = positions[rng.random_range(0..positions.len())];
+            let bad = ['@', '!', '$', ';', '%'][rng.random_range(0..5)];
+            input.replace_range(position..position + 1, &bad.to_string());
+            return input;
+        }
+        Malformation::MissingValue => {
+            let rule = rng.random_range(0..entries.len() - 1);
+            let (condition, action) = entries[rule].split_once(':').unwrap();
+            let without_value = format!("{}:{}", &condition[..2], action);
+            entries[rule] = &without_value;
+            format!("{}{{{}}}", name, entries.join(","))
+        }
+        Malformation::MissingBrace => line[..line.len() - 1].to_string(),
+        Malformation::MissingDefault => {
+            entries.pop();
+            format!("{}{{{}}}", name, entries.join(","))
+        }
+        Malformation::BareAction => {
+            let action = if rng.random() { "A" } else { "R" };
+            entries.insert(0, action);
+            format!("{}{{{}}}", name, entries.join(","))
+        }
+    };
+    lines[index] = broken;
+    lines.join("\n") + "\n"
+}
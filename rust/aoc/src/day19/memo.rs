@@ -0,0 +1,98 @@
+use std::{
+    cmp::{max, min},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use super::{
+    add_options, constrain_toy, constrain_toy_inverse, domain, get_toy_options, Action, Bounded,
+    BoundedToy, CountOverflow, WorkFlow, WorkflowName,
+};
+
+// accepted boxes per workflow, over every possible part rather than whatever
+// region a parent passed down, so each workflow is only walked once no matter
+// how many parents share it
+pub(super) struct Memo<'a> {
+    workflows: &'a HashMap<WorkflowName, WorkFlow>,
+    accepted: HashMap<&'a str, Rc<Vec<BoundedToy>>>,
+}
+
+fn intersect_bounded(a: &Bounded, b: &Bounded) -> Option<Bounded> {
+    let val = Bounded {
+        min: max(a.min, b.min),
+        max: min(a.max, b.max),
+    };
+    (val.min <= val.max).then_some(val)
+}
+
+fn intersect(a: &BoundedToy, b: &BoundedToy) -> Option<BoundedToy> {
+    Some(BoundedToy {
+        x: intersect_bounded(&a.x, &b.x)?,
+        m: intersect_bounded(&a.m, &b.m)?,
+        a: intersect_bounded(&a.a, &b.a)?,
+        s: intersect_bounded(&a.s, &b.s)?,
+    })
+}
+
+impl<'a> Memo<'a> {
+    pub(super) fn new(workflows: &'a HashMap<WorkflowName, WorkFlow>) -> Self {
+        Memo {
+            workflows,
+            accepted: HashMap::new(),
+        }
+    }
+
+    fn action_boxes(&mut self, toy: &BoundedToy, action: &'a Action, boxes: &mut Vec<BoundedToy>) {
+        match action {
+            Action::Accept => boxes.push(toy.clone()),
+            Action::Reject => {}
+            Action::Switch(name) => {
+                let accepted = self.accepted_boxes(name);
+                boxes.extend(
+                    accepted
+                        .iter()
+                        .filter_map(|accepted| intersect(toy, accepted)),
+                );
+            }
+        }
+    }
+
+    // the same walk as decided_boxes, but a jump reuses the boxes already
+    // worked out for the target workflow
+    pub(super) fn accepted_boxes(&mut self, name: &'a str) -> Rc<Vec<BoundedToy>> {
+        if let Some(accepted) = self.accepted.get(name) {
+            return accepted.clone();
+        }
+        let workflow = &self.workflows[name];
+        let mut boxes = Vec::new();
+        let mut remaining = Some(domain(0, u64::MAX));
+        for (rule, action) in &workflow.rules {
+            if let Some(toy) = remaining {
+                if let Some(matched) =
+                    constrain_toy(&toy, &rule.category, &rule.comparison, rule.val)
+                {
+                    self.action_boxes(&matched, action, &mut boxes);
+                }
+                remaining = constrain_toy_inverse(&toy, &rule.category, &rule.comparison, rule.val);
+            }
+        }
+        if let Some(toy) = remaining {
+            self.action_boxes(&toy, &workflow.default, &mut boxes);
+        }
+        let boxes = Rc::new(boxes);
+        self.accepted.insert(name, boxes.clone());
+        boxes
+    }
+
+    // the accepted combinations in `region`, same as solve from `entry`
+    pub(super) fn solve(
+        &mut self,
+        region: &BoundedToy,
+        entry: &'a str,
+    ) -> Result<u128, CountOverflow> {
+        self.accepted_boxes(entry)
+            .iter()
+            .filter_map(|accepted| intersect(region, accepted))
+            .try_fold(0, |acc, toy| add_options(acc, get_toy_options(&toy)?))
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{constrain_toy, constrain_toy_inverse, diff, domain, Action, WorkFlow, WorkflowName};
+
+// drops rules that can never match, turns a rule that always matches into the
+// default, and drops trailing rules that do the same thing as the default;
+// "never" and "always" are over every u64 rating, not just 1..=4000, since
+// parts outside that range are still accepted or rejected by the rules
+fn prune(workflow: &WorkFlow) -> WorkFlow {
+    let mut rules = Vec::new();
+    let mut default = workflow.default.clone();
+    let mut remaining = Some(domain(0, u64::MAX));
+    for (rule, action) in &workflow.rules {
+        if let Some(toy) = remaining {
+            if constrain_toy(&toy, &rule.category, &rule.comparison, rule.val).is_none() {
+                remaining = Some(toy);
+                continue;
+            }
+            remaining = constrain_toy_inverse(&toy, &rule.category, &rule.comparison, rule.val);
+            if remaining.is_some() {
+                rules.push((rule.clone(), action.clone()));
+            } else {
+                default = action.clone();
+            }
+        }
+    }
+    while rules.last().is_some_and(|(_, action)| *action == default) {
+        rules.pop();
+    }
+    WorkFlow { rules, default }
+}
+
+fn actions_mut(workflow: &mut WorkFlow) -> impl Iterator<Item = &mut Action> {
+    workflow
+        .rules
+        .iter_mut()
+        .map(|(_, action)| action)
+        .chain(std::iter::once(&mut workflow.default))
+}
+
+fn actions(workflow: &WorkFlow) -> impl Iterator<Item = &Action> {
+    workflow
+        .rules
+        .iter()
+        .map(|(_, action)| action)
+        .chain(std::iter::once(&workflow.default))
+}
+
+// replaces every reference to a workflow without rules by its default action;
+// true if anything was replaced
+fn fold_constants(workflows: &mut HashMap<WorkflowName, WorkFlow>, entry: &str) -> bool {
+    let constants: HashMap<WorkflowName, Action> = workflows
+        .iter()
+        .filter(|(name, wf)| wf.rules.is_empty() && name.as_str() != entry)
+        .map(|(name, wf)| (name.clone(), wf.default.clone()))
+        .collect();
+    let mut changed = false;
+    for workflow in workflows.values_mut() {
+        for action in actions_mut(workflow) {
+            let constant = match action {
+                Action::Switch(name) => constants.get(name),
+                _ => None,
+            };
+            // a constant that refers to itself would be replaced by itself
+            if let Some(constant) = constant.filter(|constant| *constant != action) {
+                *action = constant.clone();
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+// a workflow that is only ever reached as the default of one other workflow
+// can have its rules appended to that workflow
+fn inline_single_use(workflows: &mut HashMap<WorkflowName, WorkFlow>, entry: &str) -> bool {
+    let mut uses = HashMap::<WorkflowName, usize>::new();
+    for action in workflows.values().flat_map(actions) {
+        if let Action::Switch(name) = action {
+            *uses.entry(name.clone()).or_default() += 1;
+        }
+    }
+    let candidate = workflows.iter().find_map(|(parent, wf)| match &wf.default {
+        Action::Switch(child)
+            if child != parent && child != entry && uses.get(child) == Some(&1) =>
+        {
+            Some((parent.clone(), child.clone()))
+        }
+        _ => None,
+    });
+    if let Some((parent, child)) = candidate {
+        let WorkFlow { rules, default } = workflows.remove(&child).unwrap();
+        let parent = workflows.get_mut(&parent).unwrap();
+        parent.rules.extend(rules);
+        parent.default = default;
+        true
+    } else {
+        false
+    }
+}
+
+fn remove_unreachable(workflows: &mut HashMap<WorkflowName, WorkFlow>, entry: &str) {
+    let mut reachable = HashSet::<WorkflowName>::new();
+    let mut queue = vec![entry.to_string()];
+    while let Some(name) = queue.pop() {
+        if let Some(workflow) = workflows.get(&name) {
+            if reachable.insert(name) {
+                for action in actions(workflow) {
+                    if let Action::Switch(next) = action {
+                        queue.push(next.clone());
+                    }
+                }
+            }
+        }
+    }
+    workflows.retain(|name, _| reachable.contains(name));
+}
+
+pub(super) fn optimize(
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+) -> HashMap<WorkflowName, WorkFlow> {
+    let mut workflows: HashMap<WorkflowName, WorkFlow> = workflows
+        .iter()
+        .map(|(name, wf)| (name.clone(), prune(wf)))
+        .collect();
+    loop {
+        remove_unreachable(&mut workflows, entry);
+        let changed =
+            fold_constants(&mut workflows, entry) || inline_single_use(&mut workflows, entry);
+        if !changed {
+            return workflows;
+        }
+        for workflow in workflows.values_mut() {
+            *workflow = prune(workflow);
+        }
+    }
+}
+
+// no part, with any u64 ratings, gets a different result from the two sets
+pub(super) fn equivalent(
+    a: &HashMap<WorkflowName, WorkFlow>,
+    b: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+) -> bool {
+    diff::diff(a, b, entry, &domain(0, u64::MAX)).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{equivalent, fold_constants, inline_single_use, optimize, prune};
+    use crate::day19::{parse_workflow, parse_workflows, print::print_workflows, tests::example};
+
+    fn pruned(workflow: &str) -> String {
+        let (name, workflow) = parse_workflow(workflow).unwrap();
+        print_workflows(&[(name, prune(&workflow))].into())
+    }
+
+    #[test]
+    fn optimizes_the_example() {
+        let workflows = example();
+        let optimized = optimize(&workflows, "in");
+        assert_eq!(
+            print_workflows(&optimized),
+            "hdj{m>838:A,a>1716:R,A}
+in{s<1351:px,s>2770:A,m<1801:hdj,R}
+px{a<2006:qkq,m>2090:A,s<537:R,x>2440:R,A}
+qkq{x<1416:A,x>2662:A,R}"
+        );
+        assert!(equivalent(&workflows, &optimized, "in"));
+    }
+
+    #[test]
+    fn prunes_dead_and_redundant_rules() {
+        // both branches do the same thing
+        assert_eq!(pruned("lnx{m>1548:A,A}"), "lnx{A}");
+        // nothing is below 0, and x>3 only leads to the default anyway
+        assert_eq!(pruned("in{x<0:A,x>5:A,x>3:R,R}"), "in{x>5:A,R}");
+        // every part x<5 doesn't catch has x>4
+        assert_eq!(pruned("in{x<5:A,x>4:R,A}"), "in{x<5:A,R}");
+        // over u64 ratings, x>4000 can still match
+        assert_eq!(pruned("in{x>4000:R,A}"), "in{x>4000:R,A}");
+    }
+
+    #[test]
+    fn folds_constant_workflows() {
+        let mut workflows = parse_workflows("in{x<5:a,b}\na{R}\nb{m>3:a,A}").unwrap();
+        assert!(fold_constants(&mut workflows, "in"));
+        assert_eq!(print_workflows(&workflows), "a{R}\nb{m>3:R,A}\nin{x<5:R,b}");
+        assert!(!fold_constants(&mut workflows, "in"));
+
+        // the entry is where parts start, so it isn't folded away
+        let mut workflows = parse_workflows("in{R}\na{x<5:in,A}").unwrap();
+        assert!(!fold_constants(&mut workflows, "in"));
+    }
+
+    #[test]
+    fn inlines_workflows_used_once() {
+        let mut workflows = parse_workflows("in{x<5:A,b}\nb{m>3:A,R}").unwrap();
+        assert!(inline_single_use(&mut workflows, "in"));
+        assert_eq!(print_workflows(&workflows), "in{x<5:A,m>3:A,R}");
+        assert!(!inline_single_use(&mut workflows, "in"));
+
+        // b is also reached through a rule, so it stays
+        let mut workflows = parse_workflows("in{x<5:b,b}\nb{m>3:A,R}").unwrap();
+        assert!(!inline_single_use(&mut workflows, "in"));
+    }
+}
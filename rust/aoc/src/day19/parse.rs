@@ -0,0 +1,359 @@
+use std::{collections::HashMap, fmt, iter::Peekable, str::CharIndices};
+
+use super::{Action, Category, Comparison, Rule, Toy, WorkFlow, WorkflowName};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ParseError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl ParseError {
+    // for text that was parsed on its own but starts on `line` of a bigger
+    // input
+    pub(super) fn on_line(self, line: usize) -> ParseError {
+        ParseError {
+            line: self.line + line - 1,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Number(u64),
+    Symbol(char),
+    End,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Ident(ident) => write!(f, "`{}`", ident),
+            TokenKind::Number(number) => write!(f, "`{}`", number),
+            TokenKind::Symbol(symbol) => write!(f, "`{}`", symbol),
+            TokenKind::End => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+}
+
+impl Token {
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            message,
+        }
+    }
+}
+
+struct Lexer<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Self {
+        Lexer {
+            chars: input.char_indices().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let (_, c) = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    // whitespace (including blank lines) separates nothing in particular, and
+    // `#` starts a comment running to the end of the line
+    fn skip_trivia(&mut self) {
+        while let Some(c) = self.peek_char() {
+            if c == '#' {
+                while self.peek_char().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+            } else if c.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, ParseError> {
+        self.skip_trivia();
+        let (line, column) = (self.line, self.column);
+        let token = |kind| Token { kind, line, column };
+        let c = match self.peek_char() {
+            Some(c) => c,
+            None => return Ok(token(TokenKind::End)),
+        };
+        if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(c) = self
+                .peek_char()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            {
+                ident.push(c);
+                self.bump();
+            }
+            Ok(token(TokenKind::Ident(ident)))
+        } else if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(c) = self.peek_char().filter(|c| c.is_ascii_digit()) {
+                digits.push(c);
+                self.bump();
+            }
+            digits
+                .parse()
+                .map(|number| token(TokenKind::Number(number)))
+                .map_err(|_| token(TokenKind::End).error(format!("number {} is too large", digits)))
+        } else if "{},:<>=".contains(c) {
+            self.bump();
+            Ok(token(TokenKind::Symbol(c)))
+        } else {
+            Err(token(TokenKind::End).error(format!("unexpected character `{}`", c)))
+        }
+    }
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    current: Token,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Result<Self, ParseError> {
+        let mut lexer = Lexer::new(input);
+        let current = lexer.next_token()?;
+        Ok(Parser { lexer, current })
+    }
+
+    fn advance(&mut self) -> Result<Token, ParseError> {
+        let next = self.lexer.next_token()?;
+        Ok(std::mem::replace(&mut self.current, next))
+    }
+
+    fn at_symbol(&self, symbol: char) -> bool {
+        self.current.kind == TokenKind::Symbol(symbol)
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        self.current.error(format!(
+            "expected {}, found {}",
+            expected, self.current.kind
+        ))
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), ParseError> {
+        if self.at_symbol(symbol) {
+            self.advance()?;
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", symbol)))
+        }
+    }
+
+    fn expect_ident(&mut self, expected: &str) -> Result<(String, Token), ParseError> {
+        match self.current.kind.clone() {
+            TokenKind::Ident(ident) => Ok((ident, self.advance()?)),
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn expect_number(&mut self) -> Result<u64, ParseError> {
+        match self.current.kind {
+            TokenKind::Number(number) => {
+                self.advance()?;
+                Ok(number)
+            }
+            _ => Err(self.unexpected("a number")),
+        }
+    }
+
+    fn category(token: &Token, ident: &str) -> Result<Category, ParseError> {
+        match ident {
+            "x" => Ok(Category::X),
+            "m" => Ok(Category::M),
+            "a" => Ok(Category::A),
+            "s" => Ok(Category::S),
+            _ => Err(token.error(format!(
+                "unknown category `{}`, expected one of x, m, a, s",
+                ident
+            ))),
+        }
+    }
+
+    fn action(ident: String) -> Action {
+        match ident.as_str() {
+            "A" => Action::Accept,
+            "R" => Action::Reject,
+            _ => Action::Switch(ident),
+        }
+    }
+
+    // a<2006:qkq, or the bare default action at the end
+    fn rule_or_default(&mut self) -> Result<(Option<Rule>, Action), ParseError> {
+        let (ident, token) = self.expect_ident("a rule or action")?;
+        let comparison = if self.at_symbol('<') {
+            Comparison::LessThan
+        } else if self.at_symbol('>') {
+            Comparison::GreaterThan
+        } else {
+            return Ok((None, Self::action(ident)));
+        };
+        let category = Self::category(&token, &ident)?;
+        self.advance()?;
+        let val = self.expect_number()?;
+        self.expect_symbol(':')?;
+        let (action, _) = self.expect_ident("an action")?;
+        let rule = Rule {
+            category,
+            comparison,
+            val,
+        };
+        Ok((Some(rule), Self::action(action)))
+    }
+
+    // px{a<2006:qkq,m>2090:A,rfg}
+    fn workflow(&mut self) -> Result<(WorkflowName, WorkFlow), ParseError> {
+        let (name, token) = self.expect_ident("a workflow name")?;
+        if name == "A" || name == "R" {
+            return Err(token.error(format!("`{}` cannot be used as a workflow name", name)));
+        }
+        self.expect_symbol('{')?;
+        let mut rules = Vec::new();
+        loop {
+            let rule_token = self.current.clone();
+            match self.rule_or_default()? {
+                (Some(rule), action) => {
+                    rules.push((rule, action));
+                    self.expect_symbol(',')?;
+                }
+                (None, default) => {
+                    if !self.at_symbol('}') {
+                        return Err(if self.at_symbol(',') {
+                            rule_token.error("only the last entry can be a bare action".to_string())
+                        } else {
+                            self.unexpected("`}`")
+                        });
+                    }
+                    self.advance()?;
+                    return Ok((name, WorkFlow { rules, default }));
+                }
+            }
+        }
+    }
+
+    // {x=787,m=2655,a=1222,s=2876}, with the ratings in any order
+    fn toy(&mut self) -> Result<Toy, ParseError> {
+        let start = self.current.clone();
+        self.expect_symbol('{')?;
+        let mut ratings: [Option<u64>; 4] = [None; 4];
+        loop {
+            let (ident, token) = self.expect_ident("a category")?;
+            let index = match Self::category(&token, &ident)? {
+                Category::X => 0,
+                Category::M => 1,
+                Category::A => 2,
+                Category::S => 3,
+            };
+            if ratings[index].is_some() {
+                return Err(token.error(format!("duplicate rating for `{}`", ident)));
+            }
+            self.expect_symbol('=')?;
+            ratings[index] = Some(self.expect_number()?);
+            if self.at_symbol(',') {
+                self.advance()?;
+            } else {
+                self.expect_symbol('}')?;
+                break;
+            }
+        }
+        match ratings {
+            [Some(x), Some(m), Some(a), Some(s)] => Ok(Toy { x, m, a, s }),
+            _ => Err(start.error("part is missing a rating".to_string())),
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<(), ParseError> {
+        if self.current.kind == TokenKind::End {
+            Ok(())
+        } else {
+            Err(self.unexpected("end of input"))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Input {
+    pub(super) workflows: HashMap<WorkflowName, WorkFlow>,
+    pub(super) toys: Vec<Toy>,
+}
+
+// a whole puzzle input: workflows and parts can be told apart by their first
+// token, so the blank line between them is optional
+pub(super) fn parse_input(input: &str) -> Result<Input, ParseError> {
+    let mut parser = Parser::new(input)?;
+    let mut parsed = Input::default();
+    loop {
+        match parser.current.kind {
+            TokenKind::End => return Ok(parsed),
+            TokenKind::Symbol('{') => parsed.toys.push(parser.toy()?),
+            _ => {
+                let token = parser.current.clone();
+                let (name, workflow) = parser.workflow()?;
+                if parsed.workflows.contains_key(&name) {
+                    return Err(token.error(format!("duplicate workflow `{}`", name)));
+                }
+                parsed.workflows.insert(name, workflow);
+            }
+        }
+    }
+}
+
+pub(super) fn parse_workflow(input: &str) -> Result<(WorkflowName, WorkFlow), ParseError> {
+    let mut parser = Parser::new(input)?;
+    let workflow = parser.workflow()?;
+    parser.expect_end()?;
+    Ok(workflow)
+}
+
+pub(super) fn parse_toy(input: &str) -> Result<Toy, ParseError> {
+    let mut parser = Parser::new(input)?;
+    let toy = parser.toy()?;
+    parser.expect_end()?;
+    Ok(toy)
+}
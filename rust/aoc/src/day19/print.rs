@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use super::{Toy, WorkFlow, WorkflowName};
+
+// px{a<2006:qkq,m>2090:A,rfg}
+pub(super) fn print_workflow(name: &str, workflow: &WorkFlow) -> String {
+    let mut out = format!("{}{{", name);
+    for (rule, action) in &workflow.rules {
+        out.push_str(&format!("{}:{},", rule, action));
+    }
+    out.push_str(&format!("{}}}", workflow.default));
+    out
+}
+
+// one workflow per line, sorted by name so the output is stable
+pub(super) fn print_workflows(workflows: &HashMap<WorkflowName, WorkFlow>) -> String {
+    let mut names: Vec<&WorkflowName> = workflows.keys().collect();
+    names.sort();
+    names
+        .iter()
+        .map(|name| print_workflow(name, &workflows[*name]))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// workflows, a blank line, then one part per line, same as the puzzle input
+pub(super) fn print_input(workflows: &HashMap<WorkflowName, WorkFlow>, toys: &[Toy]) -> String {
+    let mut out = print_workflows(workflows);
+    out.push_str("\n\n");
+    for toy in toys {
+        out.push_str(&format!("{}\n", toy));
+    }
+    out
+}
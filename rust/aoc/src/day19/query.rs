@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use super::{
+    accepted_boxes, add_options, get_feasible_options, get_toy_options, min_toy, Bounded,
+    BoundedToy, CountOverflow, Toy, WorkFlow, WorkflowName,
+};
+
+fn boxes(
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+    region: &BoundedToy,
+) -> Vec<BoundedToy> {
+    accepted_boxes(region, workflows, &workflows[entry])
+}
+
+fn ranges(toy: &BoundedToy) -> [&Bounded; 4] {
+    [&toy.x, &toy.m, &toy.a, &toy.s]
+}
+
+// sum of every value in min..=max; one of (min + max) and the width is always
+// even, so halve that one first
+fn range_sum(val: &Bounded) -> Result<u128, CountOverflow> {
+    let ends = val.min as u128 + val.max as u128;
+    let width = get_feasible_options(val);
+    if ends.is_multiple_of(2) {
+        (ends / 2).checked_mul(width)
+    } else {
+        ends.checked_mul(width / 2)
+    }
+    .ok_or(CountOverflow)
+}
+
+// the largest x + m + a + s of any accepted part
+pub(super) fn max_accepted_sum(
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+    region: &BoundedToy,
+) -> Option<u128> {
+    boxes(workflows, entry, region)
+        .iter()
+        .map(|toy| ranges(toy).iter().map(|val| val.max as u128).sum())
+        .max()
+}
+
+// the smallest x + m + a + s of any accepted part
+pub(super) fn min_accepted_sum(
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+    region: &BoundedToy,
+) -> Option<u128> {
+    boxes(workflows, entry, region)
+        .iter()
+        .map(|toy| ranges(toy).iter().map(|val| val.min as u128).sum())
+        .min()
+}
+
+// x + m + a + s added up over every accepted combination; within a box each
+// value of one category shows up once for every combination of the others
+pub(super) fn total_accepted_sum(
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+    region: &BoundedToy,
+) -> Result<u128, CountOverflow> {
+    boxes(workflows, entry, region)
+        .iter()
+        .try_fold(0, |total, toy| {
+            let combinations = get_toy_options(toy)?;
+            ranges(toy).iter().try_fold(total, |total, val| {
+                let others = combinations / get_feasible_options(val);
+                let sum = range_sum(val)?.checked_mul(others).ok_or(CountOverflow)?;
+                add_options(total, sum)
+            })
+        })
+}
+
+// one accepted part (the smallest corner) for each accepted box
+pub(super) fn accepted_witnesses(
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+    region: &BoundedToy,
+) -> Vec<Toy> {
+    boxes(workflows, entry, region)
+        .iter()
+        .map(min_toy)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::HashMap;
+
+    use super::{
+        accepted_witnesses, max_accepted_sum, min_accepted_sum, range_sum, total_accepted_sum,
+    };
+    use crate::day19::{
+        domain,
+        generate::{generate, GenConfig},
+        handle_workflow, parse_region, sum,
+        tests::example,
+        Bounded, BoundedToy, Toy, WorkFlow, WorkFlowResult, WorkflowName,
+    };
+
+    fn in_region(toy: &Toy, region: &BoundedToy) -> bool {
+        let within = |val: u64, bounded: &Bounded| bounded.min <= val && val <= bounded.max;
+        within(toy.x, &region.x)
+            && within(toy.m, &region.m)
+            && within(toy.a, &region.a)
+            && within(toy.s, &region.s)
+    }
+
+    // every query against running each part of the (small) region through
+    // handle_workflow
+    fn check_queries(workflows: &HashMap<WorkflowName, WorkFlow>, region: &BoundedToy, case: &str) {
+        let accepted = |toy: &Toy| {
+            handle_workflow(workflows, &workflows["in"], toy) == WorkFlowResult::Accepted
+        };
+        let (mut max, mut min, mut total) = (None, None, 0);
+        for x in region.x.min..=region.x.max {
+            for m in region.m.min..=region.m.max {
+                for a in region.a.min..=region.a.max {
+                    for s in region.s.min..=region.s.max {
+                        let toy = Toy { x, m, a, s };
+                        if accepted(&toy) {
+                            let sum = sum(&toy);
+                            max = max.max(Some(sum));
+                            min = Some(min.map_or(sum, |min: u128| min.min(sum)));
+                            total += sum;
+                        }
+                    }
+                }
+            }
+        }
+        assert_eq!(max_accepted_sum(workflows, "in", region), max, "{}", case);
+        assert_eq!(min_accepted_sum(workflows, "in", region), min, "{}", case);
+        assert_eq!(
+            total_accepted_sum(workflows, "in", region),
+            Ok(total),
+            "{}",
+            case
+        );
+
+        let witnesses = accepted_witnesses(workflows, "in", region);
+        for toy in &witnesses {
+            assert!(
+                accepted(toy) && in_region(toy, region),
+                "{}: witness {}",
+                case,
+                toy
+            );
+        }
+        // the boxes are disjoint, so no two share a corner
+        for (i, toy) in witnesses.iter().enumerate() {
+            assert!(
+                !witnesses[..i].contains(toy),
+                "{}: repeated witness {}",
+                case,
+                toy
+            );
+        }
+        assert_eq!(witnesses.iter().map(sum).min(), min, "{}", case);
+        assert_eq!(witnesses.is_empty(), max.is_none(), "{}", case);
+    }
+
+    #[test]
+    fn example_matches_brute_force() {
+        let workflows = example();
+        // small boxes around the thresholds of different paths
+        let regions = [
+            "x=1410..=1420 m=833..=843 a=2000..=2010 s=1345..=1355",
+            "x=2435..=2445 m=1795..=1805 a=1711..=1721 s=2765..=2775",
+            "x=2657..=2667 m=2085..=2095 a=3328..=3338 s=532..=542",
+        ];
+        for region in regions {
+            let region = parse_region(region.split(' ')).unwrap().unwrap();
+            check_queries(&workflows, &region, &region.to_string());
+        }
+    }
+
+    #[test]
+    fn generated_workflows_match_brute_force() {
+        for seed in 0..50 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let config = GenConfig {
+                depth: 3,
+                fan_out: 4,
+                domain_max: 8,
+                parts: 0,
+            };
+            let (workflows, _) = generate(&mut rng, &config);
+            check_queries(&workflows, &domain(1, 8), &format!("seed {}", seed));
+        }
+    }
+
+    #[test]
+    fn range_sums() {
+        for min in 0..20 {
+            for max in min..20 {
+                let val = Bounded { min, max };
+                assert_eq!(range_sum(&val), Ok((min..=max).map(u128::from).sum()));
+            }
+        }
+        let full = Bounded {
+            min: 0,
+            max: u64::MAX,
+        };
+        assert_eq!(range_sum(&full), Ok(((1u128 << 64) - 1) << 63));
+        let top = Bounded {
+            min: u64::MAX,
+            max: u64::MAX,
+        };
+        assert_eq!(range_sum(&top), Ok(u64::MAX as u128));
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use super::{constrain_toy, constrain_toy_inverse, Action, BoundedToy, WorkFlow, WorkflowName};
+
+// every box of parts that arrives at each workflow, and every box decided by
+// each of its branches; branch `i` is rule `i` and branch `rules.len()` is
+// the default, so a workflow has one more branch than it has rules
+pub(super) struct Reach<'a> {
+    workflows: HashMap<&'a str, Vec<BoundedToy>>,
+    branches: HashMap<&'a str, Vec<Vec<BoundedToy>>>,
+}
+
+// a branch of a workflow, for reporting
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Branch<'a> {
+    pub(super) workflow: &'a str,
+    pub(super) index: usize,
+    pub(super) is_default: bool,
+}
+
+fn walk<'a>(
+    reach: &mut Reach<'a>,
+    workflows: &'a HashMap<WorkflowName, WorkFlow>,
+    name: &'a str,
+    toy: &BoundedToy,
+) {
+    reach.workflows.entry(name).or_default().push(toy.clone());
+    let workflow = &workflows[name];
+    let mut remaining = Some(toy.clone());
+    for (i, (rule, action)) in workflow.rules.iter().enumerate() {
+        if let Some(toy) = remaining {
+            if let Some(matched) = constrain_toy(&toy, &rule.category, &rule.comparison, rule.val) {
+                decide(reach, workflows, (name, i), &matched, action);
+            }
+            remaining = constrain_toy_inverse(&toy, &rule.category, &rule.comparison, rule.val);
+        }
+    }
+    if let Some(toy) = remaining {
+        decide(
+            reach,
+            workflows,
+            (name, workflow.rules.len()),
+            &toy,
+            &workflow.default,
+        );
+    }
+}
+
+fn decide<'a>(
+    reach: &mut Reach<'a>,
+    workflows: &'a HashMap<WorkflowName, WorkFlow>,
+    branch: (&'a str, usize),
+    toy: &BoundedToy,
+    action: &'a Action,
+) {
+    let (name, index) = branch;
+    let branches = reach
+        .branches
+        .entry(name)
+        .or_insert_with(|| vec![Vec::new(); workflows[name].rules.len() + 1]);
+    branches[index].push(toy.clone());
+    if let Action::Switch(next) = action {
+        walk(reach, workflows, next, toy);
+    }
+}
+
+// the same propagation as decided_boxes, keeping every intermediate box;
+// the workflows must be valid from `entry`
+pub(super) fn reach<'a>(
+    workflows: &'a HashMap<WorkflowName, WorkFlow>,
+    entry: &'a str,
+    region: &BoundedToy,
+) -> Reach<'a> {
+    let mut reach = Reach {
+        workflows: HashMap::new(),
+        branches: HashMap::new(),
+    };
+    walk(&mut reach, workflows, entry, region);
+    reach
+}
+
+impl<'a> Reach<'a> {
+    // the disjoint boxes of parts that pass through `name`
+    pub(super) fn workflow(&self, name: &str) -> &[BoundedToy] {
+        self.workflows.get(name).map_or(&[], |boxes| boxes)
+    }
+
+    // the disjoint boxes of parts decided by branch `index` of `name`
+    pub(super) fn branch(&self, name: &str, index: usize) -> &[BoundedToy] {
+        self.branches
+            .get(name)
+            .and_then(|branches| branches.get(index))
+            .map_or(&[], |boxes| boxes)
+    }
+
+    // branches no part in the region ever takes, sorted by workflow
+    pub(super) fn unreachable(
+        &self,
+        workflows: &'a HashMap<WorkflowName, WorkFlow>,
+    ) -> Vec<Branch<'a>> {
+        let mut names: Vec<&WorkflowName> = workflows.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .flat_map(|name| {
+                let rules = workflows[name].rules.len();
+                (0..=rules).map(move |index| Branch {
+                    workflow: name.as_str(),
+                    index,
+                    is_default: index == rules,
+                })
+            })
+            .filter(|branch| self.branch(branch.workflow, branch.index).is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reach, Branch};
+    use crate::day19::{parse_region, parse_workflows, tests::example, total_options, BoundedToy};
+
+    #[test]
+    fn parts_reaching_a_workflow() {
+        let workflows = example();
+        let reach = reach(&workflows, "in", &BoundedToy::default());
+        // rfg is only reached through px's default
+        assert_eq!(total_options(reach.workflow("rfg")), Ok(22515570000000));
+        assert_eq!(reach.workflow("rfg"), reach.branch("px", 2));
+        assert_eq!(
+            reach.workflow("in"),
+            std::slice::from_ref(&BoundedToy::default())
+        );
+        assert!(reach.workflow("nope").is_empty());
+    }
+
+    #[test]
+    fn parts_decided_by_a_rule() {
+        let workflows = example();
+        let reach = reach(&workflows, "in", &BoundedToy::default());
+        let expected = parse_region(["m>2090", "a>2005", "s<1351"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(reach.branch("px", 1), [expected]);
+        assert!(reach.unreachable(&workflows).is_empty());
+    }
+
+    #[test]
+    fn shadowed_rules_are_unreachable() {
+        let workflows = parse_workflows("in{x>10:A,x>20:R,R}\nlost{A}").unwrap();
+        let reach = reach(&workflows, "in", &BoundedToy::default());
+        assert_eq!(
+            reach.unreachable(&workflows),
+            vec![
+                Branch {
+                    workflow: "in",
+                    index: 1,
+                    is_default: false,
+                },
+                Branch {
+                    workflow: "lost",
+                    index: 0,
+                    is_default: true,
+                },
+            ]
+        );
+    }
+}
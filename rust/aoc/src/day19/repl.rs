@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use super::{
+    handle_workflow, parse_region, parse_toy, parse_workflow, print, solve_from, sum,
+    validate::validate, Category, Toy, WorkFlow, WorkFlowResult, WorkflowName,
+};
+
+const HELP: &str = "commands:
+  eval x=1 m=2 a=3 s=4      run a part through the workflows starting at in
+  count [workflow] [where x<100 m=1..=50 ...]
+                            count accepted combinations, optionally starting
+                            at another workflow and within narrower ranges
+  list                      list workflow names
+  show <workflow>           show a workflow's rules
+  set <name>{rules}         add or replace a workflow
+  remove <workflow>         remove a workflow, e.g. one that was set by mistake
+  undo                      take back the last set or remove, e.g. to get the
+                            original in back after replacing it
+  help                      show this message
+  quit                      leave";
+
+struct Session {
+    workflows: HashMap<WorkflowName, WorkFlow>,
+    // what each set or remove overwrote, latest last; None for a workflow that
+    // didn't exist before
+    history: Vec<(WorkflowName, Option<WorkFlow>)>,
+}
+
+fn parse_category(category: &str) -> Result<Category, String> {
+    match category {
+        "x" => Ok(Category::X),
+        "m" => Ok(Category::M),
+        "a" => Ok(Category::A),
+        "s" => Ok(Category::S),
+        _ => Err(format!("unknown category {}", category)),
+    }
+}
+
+// x=1 m=2 a=3 s=4 in any order, or the puzzle's {x=1,m=2,a=3,s=4}
+fn parse_eval_toy(args: &[&str]) -> Result<Toy, String> {
+    if args.first().is_some_and(|arg| arg.starts_with('{')) {
+        return parse_toy(&args.join(" ")).map_err(|err| err.to_string());
+    }
+    let mut ratings: [Option<u64>; 4] = [None; 4];
+    for arg in args {
+        let (category, val) = arg
+            .split_once('=')
+            .ok_or(format!("expected category=value, got {}", arg))?;
+        let index = match parse_category(category)? {
+            Category::X => 0,
+            Category::M => 1,
+            Category::A => 2,
+            Category::S => 3,
+        };
+        ratings[index] = Some(val.parse().map_err(|_| format!("invalid value {}", val))?);
+    }
+    match ratings {
+        [Some(x), Some(m), Some(a), Some(s)] => Ok(Toy { x, m, a, s }),
+        _ => Err("eval needs x, m, a and s".to_string()),
+    }
+}
+
+impl Session {
+    fn check_valid(&self) -> Result<(), String> {
+        validate(&self.workflows, "in").map_err(|err| format!("workflows are invalid: {}", err))
+    }
+
+    fn eval(&self, args: &[&str]) -> Result<String, String> {
+        self.check_valid()?;
+        let toy = parse_eval_toy(args)?;
+        let res = handle_workflow(&self.workflows, &self.workflows["in"], &toy);
+        Ok(match res {
+            WorkFlowResult::Accepted => format!("Accepted (rating sum {})", sum(&toy)),
+            WorkFlowResult::Rejected => "Rejected".to_string(),
+        })
+    }
+
+    fn count(&self, args: &[&str]) -> Result<String, String> {
+        self.check_valid()?;
+        let (name, conditions) = match args {
+            [] => ("in", &args[..0]),
+            ["where", conditions @ ..] => ("in", conditions),
+            [name] => (*name, &args[..0]),
+            [name, "where", conditions @ ..] => (*name, conditions),
+            _ => return Err("usage: count [workflow] [where x<100 ...]".to_string()),
+        };
+        let region = parse_region(
+            conditions
+                .iter()
+                .copied()
+                .filter(|condition| *condition != "and"),
+        )?;
+        match region {
+            Some(region) => solve_from(&self.workflows, name, &region)
+                .map(|count| count.to_string())
+                .map_err(|err| err.to_string()),
+            None => Ok("0".to_string()),
+        }
+    }
+
+    fn show(&self, name: &str) -> Result<String, String> {
+        self.workflows
+            .get(name)
+            .map(|workflow| print::print_workflow(name, workflow))
+            .ok_or(format!("unknown workflow {}", name))
+    }
+
+    // a change is made even when it leaves the set invalid, with a warning
+    fn revalidate(&self, done: String) -> String {
+        match validate(&self.workflows, "in") {
+            Ok(()) => done,
+            Err(err) => format!("{}, but workflows are invalid: {}", done, err),
+        }
+    }
+
+    // the new workflow is kept even when the set doesn't validate yet, so
+    // workflows that refer to each other can be added one at a time; a
+    // mistaken one can be taken back with undo or remove
+    fn set(&mut self, definition: &str) -> Result<String, String> {
+        let (name, workflow) = parse_workflow(definition).map_err(|err| err.to_string())?;
+        let previous = self.workflows.insert(name.clone(), workflow);
+        let verb = if previous.is_some() {
+            "replaced"
+        } else {
+            "added"
+        };
+        self.history.push((name.clone(), previous));
+        Ok(self.revalidate(format!("{} {}", verb, name)))
+    }
+
+    fn remove(&mut self, name: &str) -> Result<String, String> {
+        if name == "in" {
+            return Err("in is where every part starts; set it to replace it".to_string());
+        }
+        let previous = self
+            .workflows
+            .remove(name)
+            .ok_or(format!("unknown workflow {}", name))?;
+        self.history.push((name.to_string(), Some(previous)));
+        Ok(self.revalidate(format!("removed {}", name)))
+    }
+
+    fn undo(&mut self) -> Result<String, String> {
+        let (name, previous) = self.history.pop().ok_or("nothing to undo".to_string())?;
+        let done = match previous {
+            Some(workflow) => {
+                self.workflows.insert(name.clone(), workflow);
+                format!("restored {}", name)
+            }
+            None => {
+                self.workflows.remove(&name);
+                format!("removed {}", name)
+            }
+        };
+        Ok(self.revalidate(done))
+    }
+
+    fn execute(&mut self, line: &str) -> Result<String, String> {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        match args[..] {
+            ["eval", ..] => self.eval(&args[1..]),
+            ["count", ..] => self.count(&args[1..]),
+            ["list"] => {
+                let mut names: Vec<&WorkflowName> = self.workflows.keys().collect();
+                names.sort();
+                Ok(names
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(" "))
+            }
+            ["show", name] => self.show(name),
+            ["set", ..] => self.set(line.trim_start()["set".len()..].trim()),
+            ["remove", name] => self.remove(name),
+            ["undo"] => self.undo(),
+            ["help"] => Ok(HELP.to_string()),
+            _ => Err(format!("unknown command: {} (try help)", line.trim())),
+        }
+    }
+}
+
+pub(super) fn run(
+    workflows: HashMap<WorkflowName, WorkFlow>,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    let mut session = Session {
+        workflows,
+        history: Vec::new(),
+    };
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        match line.trim() {
+            "" => {}
+            "quit" | "exit" => return Ok(()),
+            _ => match session.execute(&line) {
+                Ok(out) => writeln!(output, "{}", out)?,
+                Err(err) => writeln!(output, "error: {}", err)?,
+            },
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+    use crate::day19::tests::example;
+
+    fn session(script: &str) -> String {
+        let mut output = Vec::new();
+        run(example(), script.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn scripted_session() {
+        let script = "eval x=787 m=2655 a=1222 s=2876
+eval {x=1679,m=44,a=2067,s=496}
+count qkq where x=1..=100
+count qkq where x<100
+list
+show px
+set px{a<2006:nope,R}
+eval x=787 m=2655 a=1222 s=2876
+remove nope
+undo
+remove crn
+undo
+remove in
+set in{A}
+count
+undo
+count
+undo
+quit
+count
+";
+        let transcript = [
+            "Accepted (rating sum 7540)",
+            "Rejected",
+            "6400000000000",
+            "6336000000000",
+            "crn gd hdj in lnx pv px qkq qqz qs rfg",
+            "px{a<2006:qkq,m>2090:A,rfg}",
+            "replaced px, but workflows are invalid: px refers to unknown workflow nope",
+            "error: workflows are invalid: px refers to unknown workflow nope",
+            "error: unknown workflow nope",
+            "restored px",
+            "removed crn, but workflows are invalid: qkq refers to unknown workflow crn",
+            "restored crn",
+            "error: in is where every part starts; set it to replace it",
+            "replaced in",
+            "256000000000000",
+            "restored in",
+            "167409079868000",
+            "error: nothing to undo",
+        ];
+        let expected: String = transcript
+            .iter()
+            .map(|line| format!("> {}\n", line))
+            .collect();
+        // quit stops before the last count
+        assert_eq!(session(script), expected + "> ");
+    }
+
+    #[test]
+    fn set_can_add_workflows_one_at_a_time() {
+        // only x=10..=19 is accepted once new exists
+        let script = "set in{x<10:new,A}
+set new{R}
+count where x<20
+remove new
+";
+        assert_eq!(
+            session(script),
+            "> replaced in, but workflows are invalid: in refers to unknown workflow new
+> added new
+> 640000000000
+> removed new, but workflows are invalid: in refers to unknown workflow new
+> \n"
+        );
+    }
+}
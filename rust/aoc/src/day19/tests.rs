@@ -0,0 +1,465 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashMap;
+
+use super::{
+    compile, constrain, constrain_inverse, domain,
+    generate::{
+        generate, malform, random_name, random_rule, random_toy, render, Fault, GenConfig,
+        MALFORMATIONS,
+    },
+    get_toy_options, handle_workflow,
+    memo::Memo,
+    narrow, parse, parse_region, parse_toy, parse_workflows, print, solve, solve_from, solve_lines,
+    validate::{validate, ValidationError},
+    Action, Bounded, BoundedToy, Comparison, CountOverflow, SolveError, Toy, WorkFlow,
+    WorkFlowResult, WorkflowName,
+};
+
+// each seed is its own case, so a failure names the seed to replay it with
+const SEEDS: u64 = 200;
+
+// the workflows from the puzzle description
+pub(super) const EXAMPLE: &str = "px{a<2006:qkq,m>2090:A,rfg}
+pv{a>1716:R,A}
+lnx{m>1548:A,A}
+rfg{s<537:gd,x>2440:R,A}
+qs{s>3448:A,lnx}
+qkq{x<1416:A,crn}
+crn{x>2662:A,R}
+in{s<1351:px,qqz}
+qqz{s>2770:qs,m<1801:hdj,R}
+gd{a>3333:R,R}
+hdj{m>838:A,pv}";
+
+pub(super) fn example() -> HashMap<WorkflowName, WorkFlow> {
+    parse_workflows(EXAMPLE).unwrap()
+}
+
+fn random_action(rng: &mut StdRng, names: &[WorkflowName]) -> Action {
+    match rng.random_range(0..3) {
+        0 => Action::Accept,
+        1 => Action::Reject,
+        _ => Action::Switch(names[rng.random_range(0..names.len())].clone()),
+    }
+}
+
+// arbitrary workflows, only meant to exercise the syntax: they can reference
+// each other in cycles
+fn random_workflows(rng: &mut StdRng) -> HashMap<WorkflowName, WorkFlow> {
+    let names: Vec<WorkflowName> = (0..rng.random_range(1..8))
+        .map(|_| random_name(rng))
+        .collect();
+    names
+        .iter()
+        .map(|name| {
+            let rules = (0..rng.random_range(0..5))
+                .map(|_| (random_rule(rng, 4000), random_action(rng, &names)))
+                .collect();
+            let default = random_action(rng, &names);
+            (name.clone(), WorkFlow { rules, default })
+        })
+        .collect()
+}
+
+#[test]
+fn workflows_and_parts_round_trip() {
+    for seed in 0..SEEDS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let workflows = random_workflows(&mut rng);
+        let printed = print::print_workflows(&workflows);
+        assert_eq!(
+            parse_workflows(&printed).as_ref(),
+            Ok(&workflows),
+            "seed {}: workflows did not round trip:\n{}",
+            seed,
+            printed
+        );
+        let toy = random_toy(&mut rng, 4000);
+        let printed = toy.to_string();
+        assert_eq!(
+            parse_toy(&printed).as_ref(),
+            Ok(&toy),
+            "seed {}: part did not round trip: {}",
+            seed,
+            printed
+        );
+    }
+}
+
+// (input, line, column) of the first error in each malformed input
+const MALFORMED: &[(&str, usize, usize)] = &[
+    ("px{a<2006:qkq,m>2090:A,rfg", 1, 27),
+    ("px{a<2006:qkq,m>2090:A rfg}", 1, 24),
+    ("px{q<2006:qkq,A}", 1, 4),
+    ("px{a<:qkq,A}", 1, 6),
+    ("px{a<2006:qkq,A,R}", 1, 15),
+    ("px{a<99999999999999999999:A,R}", 1, 6),
+    ("in{A}\n# comment\n  px{a<2006;qkq,A}", 3, 12),
+    ("in{A}\n\n{x=1,m=2,a=3}", 3, 1),
+    ("in{A}\n\n{x=1,m=2,x=3,s=4}", 3, 10),
+    ("in{A}\nin{R}", 2, 1),
+    ("A{R}", 1, 1),
+    ("px{a<2006:qkq,m>2090:A,rfg}\n{x=1,m=2,a=3,s=4}!", 2, 18),
+];
+
+#[test]
+fn malformed_input_errors_point_at_the_first_mistake() {
+    for (input, line, column) in MALFORMED {
+        let expected = format!("line {}, column {}:", line, column);
+        match parse::parse_input(input) {
+            Ok(_) => panic!("{:?} should not parse", input),
+            Err(err) => assert!(
+                err.to_string().starts_with(&expected),
+                "{:?}: expected an error at {} got {}",
+                input,
+                expected,
+                err
+            ),
+        }
+    }
+}
+
+fn solve_rules(rules: &str, toy: &BoundedToy) -> Result<u128, CountOverflow> {
+    let workflows = parse_workflows(rules).unwrap();
+    solve(toy, &workflows, &workflows["in"])
+}
+
+#[test]
+fn constraints_at_the_puzzle_edges() {
+    use Comparison::{GreaterThan, LessThan};
+    let bounded = |min, max| Bounded { min, max };
+    let puzzle = bounded(1, 4000);
+
+    assert_eq!(constrain(&puzzle, &LessThan, 1), None);
+    assert_eq!(constrain(&puzzle, &LessThan, 2), Some(bounded(1, 1)));
+    assert_eq!(constrain(&puzzle, &LessThan, 4001), Some(puzzle.clone()));
+    assert_eq!(constrain(&puzzle, &GreaterThan, 0), Some(puzzle.clone()));
+    assert_eq!(
+        constrain(&puzzle, &GreaterThan, 3999),
+        Some(bounded(4000, 4000))
+    );
+    assert_eq!(constrain(&puzzle, &GreaterThan, 4000), None);
+    assert_eq!(
+        constrain_inverse(&puzzle, &LessThan, 1),
+        Some(puzzle.clone())
+    );
+    assert_eq!(
+        constrain_inverse(&puzzle, &GreaterThan, 4000),
+        Some(puzzle.clone())
+    );
+    assert_eq!(
+        constrain_inverse(&puzzle, &GreaterThan, 3999),
+        Some(bounded(1, 3999))
+    );
+}
+
+#[test]
+fn constraints_at_the_u64_edges() {
+    use Comparison::{GreaterThan, LessThan};
+    let bounded = |min, max| Bounded { min, max };
+    let full = bounded(0, u64::MAX);
+
+    assert_eq!(constrain(&full, &LessThan, 0), None);
+    assert_eq!(constrain(&full, &LessThan, 1), Some(bounded(0, 0)));
+    assert_eq!(constrain_inverse(&full, &LessThan, 0), Some(full.clone()));
+    assert_eq!(constrain(&full, &GreaterThan, u64::MAX), None);
+    assert_eq!(
+        constrain_inverse(&full, &GreaterThan, u64::MAX),
+        Some(full.clone())
+    );
+    assert_eq!(
+        constrain(&full, &LessThan, u64::MAX),
+        Some(bounded(0, u64::MAX - 1))
+    );
+}
+
+#[test]
+fn counts_up_to_u128_overflow() {
+    let full = Bounded {
+        min: 0,
+        max: u64::MAX,
+    };
+    let one_wide = BoundedToy {
+        x: full.clone(),
+        ..domain(7, 7)
+    };
+    let two_wide = BoundedToy {
+        x: full.clone(),
+        m: full.clone(),
+        ..domain(7, 7)
+    };
+    assert_eq!(get_toy_options(&BoundedToy::default()), Ok(4000u128.pow(4)));
+    assert_eq!(get_toy_options(&domain(0, 0)), Ok(1));
+    assert_eq!(get_toy_options(&one_wide), Ok(1 << 64));
+    assert_eq!(get_toy_options(&two_wide), Err(CountOverflow));
+
+    assert_eq!(
+        solve_rules("in{A}", &BoundedToy::default()),
+        Ok(4000u128.pow(4))
+    );
+    assert_eq!(
+        solve_rules("in{A}", &domain(0, u64::MAX)),
+        Err(CountOverflow)
+    );
+    assert_eq!(solve_rules("in{x<1:A,R}", &one_wide), Ok(1));
+    let x_wide = BoundedToy {
+        x: full.clone(),
+        ..domain(0, 1)
+    };
+    assert_eq!(solve_rules("in{x<1:A,R}", &x_wide), Ok(8));
+    assert_eq!(
+        solve_rules("in{x>0:R,A}", &domain(0, u64::MAX)),
+        Err(CountOverflow)
+    );
+}
+
+// solve on 1..=8 must agree with running every single part in the domain
+// through handle_workflow, the compiled program and the memoized solve
+#[test]
+fn solve_matches_brute_force() {
+    let max = 8;
+    for seed in 0..SEEDS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let config = GenConfig {
+            depth: 3,
+            fan_out: 4,
+            domain_max: max,
+            parts: 0,
+        };
+        let (workflows, _) = generate(&mut rng, &config);
+        let printed = print::print_workflows(&workflows);
+        let in_wf = &workflows["in"];
+        let program = compile::compile(&workflows, "in").unwrap();
+        let solved = solve(&domain(1, max), &workflows, in_wf).unwrap();
+        let memoized = Memo::new(&workflows).solve(&domain(1, max), "in").unwrap();
+        assert_eq!(
+            memoized, solved,
+            "seed {}: memoized solve for\n{}",
+            seed, printed
+        );
+        let mut counted: u128 = 0;
+        for x in 1..=max {
+            for m in 1..=max {
+                for a in 1..=max {
+                    for s in 1..=max {
+                        let toy = Toy { x, m, a, s };
+                        let res = handle_workflow(&workflows, in_wf, &toy);
+                        assert_eq!(
+                            program.evaluate(&toy),
+                            res,
+                            "seed {}: compiled program on {} for\n{}",
+                            seed,
+                            toy,
+                            printed
+                        );
+                        if res == WorkFlowResult::Accepted {
+                            counted += 1;
+                        }
+                    }
+                }
+            }
+        }
+        assert_eq!(solved, counted, "seed {}: solve for\n{}", seed, printed);
+    }
+}
+
+// generated sets are valid and come back unchanged through the printer and
+// parser; each kind of injected fault is caught by the parser or validator
+#[test]
+fn generated_inputs_round_trip_and_faults_are_caught() {
+    for seed in 0..SEEDS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (workflows, toys) = generate(&mut rng, &GenConfig::default());
+        let input = render(&mut rng, &workflows, &toys, None);
+        let parsed = parse::parse_input(&input)
+            .unwrap_or_else(|err| panic!("seed {}: {}\n{}", seed, err, input));
+        assert!(
+            parsed.workflows == workflows && parsed.toys == toys,
+            "seed {}: generated input did not round trip:\n{}",
+            seed,
+            input
+        );
+        assert_eq!(
+            validate(&workflows, "in"),
+            Ok(()),
+            "seed {}:\n{}",
+            seed,
+            input
+        );
+
+        for fault in [
+            Fault::DanglingReference,
+            Fault::Cycle,
+            Fault::MalformedToken,
+        ] {
+            let input = render(&mut rng, &workflows, &toys, Some(fault));
+            let detected = match (fault, parse::parse_input(&input)) {
+                (Fault::MalformedToken, parsed) => parsed.is_err(),
+                (_, Err(_)) => false,
+                (Fault::DanglingReference, Ok(parsed)) => matches!(
+                    validate(&parsed.workflows, "in"),
+                    Err(ValidationError::DanglingReference { .. })
+                ),
+                (Fault::Cycle, Ok(parsed)) => matches!(
+                    validate(&parsed.workflows, "in"),
+                    Err(ValidationError::Cycle(_))
+                ),
+            };
+            assert!(
+                detected,
+                "seed {}: {:?} went undetected in\n{}",
+                seed, fault, input
+            );
+        }
+    }
+}
+
+// every way of malforming a generated input is a parse error, not just a
+// stray character
+#[test]
+fn each_malformation_is_a_parse_error() {
+    for seed in 0..SEEDS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (workflows, toys) = generate(&mut rng, &GenConfig::default());
+        let input = render(&mut rng, &workflows, &toys, None);
+        for malformation in MALFORMATIONS {
+            let broken = malform(&mut rng, &input, malformation);
+            assert!(
+                parse::parse_input(&broken).is_err(),
+                "seed {}: {:?} still parses:\n{}",
+                seed,
+                malformation,
+                broken
+            );
+        }
+    }
+}
+
+#[test]
+fn regions_narrow_like_rules() {
+    let bounded = |min, max| Bounded { min, max };
+    let puzzle = BoundedToy::default();
+    let narrowed = |condition| narrow(&puzzle, condition).unwrap().map(|toy| toy.x);
+
+    assert_eq!(narrowed("x<100"), Some(bounded(1, 99)));
+    assert_eq!(narrowed("x>5"), Some(bounded(6, 4000)));
+    assert_eq!(narrowed("x=7"), Some(bounded(7, 7)));
+    assert_eq!(narrowed("x=1..=100"), Some(bounded(1, 100)));
+    assert_eq!(narrowed("x=3000..=5000"), Some(bounded(3000, 4000)));
+    assert_eq!(narrowed("x<1"), None);
+    assert_eq!(narrowed("x=5..=4"), None);
+    assert_eq!(
+        narrow(&puzzle, "s>10").unwrap(),
+        Some(BoundedToy {
+            s: bounded(11, 4000),
+            ..puzzle.clone()
+        })
+    );
+
+    for (condition, err) in [
+        ("x", "expected a condition like x<100 or x=1..=100, got x"),
+        ("q<5", "unknown category q in q<5"),
+        ("x<five", "invalid value five in x<five"),
+        ("x=1..100", "invalid value 1..100 in x=1..100"),
+        ("x<-1", "invalid value -1 in x<-1"),
+    ] {
+        assert_eq!(narrow(&puzzle, condition), Err(err.to_string()));
+    }
+}
+
+#[test]
+fn regions_at_the_u64_edges() {
+    let bounded = |min, max| Bounded { min, max };
+    let full = domain(0, u64::MAX);
+    let narrowed = |condition| narrow(&full, condition).unwrap().map(|toy| toy.x);
+
+    assert_eq!(narrowed("x=0..=18446744073709551615"), Some(full.x.clone()));
+    assert_eq!(narrowed("x=0"), Some(bounded(0, 0)));
+    assert_eq!(
+        narrowed("x=18446744073709551615"),
+        Some(bounded(u64::MAX, u64::MAX))
+    );
+    assert_eq!(narrowed("x<0"), None);
+    assert_eq!(narrowed("x>18446744073709551615"), None);
+    assert_eq!(
+        narrow(&full, "x=18446744073709551616"),
+        Err("invalid value 18446744073709551616 in x=18446744073709551616".to_string())
+    );
+}
+
+#[test]
+fn regions_from_conditions() {
+    assert_eq!(parse_region([]), Ok(Some(BoundedToy::default())));
+    assert_eq!(
+        parse_region(["x=1..=100", "m<2000", "x>50"]),
+        Ok(Some(BoundedToy {
+            x: Bounded { min: 51, max: 100 },
+            m: Bounded { min: 1, max: 1999 },
+            ..BoundedToy::default()
+        }))
+    );
+    // once the region is empty the rest isn't looked at
+    assert_eq!(parse_region(["x<1", "bogus"]), Ok(None));
+    assert!(parse_region(["bogus", "x<1"]).is_err());
+}
+
+#[test]
+fn solve_from_any_entry_and_region() {
+    let workflows = example();
+    let region = parse_region(["x=1..=100"]).unwrap().unwrap();
+    // every qkq part with x<1416 is accepted
+    assert_eq!(
+        solve_from(&workflows, "qkq", &region),
+        Ok(100 * 4000u128.pow(3))
+    );
+    assert_eq!(
+        solve_from(&workflows, "in", &BoundedToy::default()),
+        Ok(167409079868000)
+    );
+    let err = solve_from(&workflows, "nope", &region).unwrap_err();
+    assert_eq!(
+        err,
+        SolveError::Invalid(ValidationError::MissingEntry("nope".to_string()))
+    );
+    assert_eq!(err.to_string(), "unknown entry workflow nope");
+}
+
+#[test]
+fn streamed_input() {
+    let parts = "{x=787,m=2655,a=1222,s=2876}
+{x=1679,m=44,a=2067,s=496}
+{x=2036,m=264,a=79,s=2244}
+{x=2461,m=1339,a=466,s=291}
+{x=2127,m=1623,a=2188,s=1013}";
+    let input = format!("# the example\n{}\n\n  # parts\n{}\n", EXAMPLE, parts);
+    assert_eq!(solve_lines(input.as_bytes()), Ok((19114, 167409079868000)));
+
+    for (input, err) in [
+        (
+            "in{A}\n{x=1,m=2,a=3,s=4}\npx{R}",
+            "line 3: workflow after the parts",
+        ),
+        ("in{A}\n# again\nin{R}", "line 3: duplicate workflow `in`"),
+        (
+            "in{A}\n\n{x=1,m=2,a=3}",
+            "line 3, column 1: part is missing a rating",
+        ),
+        (
+            "\n\n  px{a<2006;qkq,A}",
+            "line 3, column 12: unexpected character `;`",
+        ),
+        ("px{A}\n{x=1,m=2,a=3,s=4}", "missing in workflow"),
+        ("px{A}", "unknown entry workflow in"),
+        (
+            "in{x<5:px,A}\n{x=1,m=2,a=3,s=4}",
+            "in refers to unknown workflow px",
+        ),
+    ] {
+        assert_eq!(
+            solve_lines(input.as_bytes()),
+            Err(err.to_string()),
+            "{:?}",
+            input
+        );
+    }
+}
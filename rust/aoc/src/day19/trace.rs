@@ -0,0 +1,282 @@
+use serde_json::{json, Value};
+use std::{collections::HashMap, fmt};
+
+use super::{
+    get, match_rule, validate::ValidationError, Action, Rule, Toy, WorkFlow, WorkFlowResult,
+    WorkflowName,
+};
+
+// a single rule tested against the part, with the value it was tested on
+#[derive(Debug)]
+pub(super) struct RuleCheck<'a> {
+    rule: &'a Rule,
+    value: u64,
+    matched: bool,
+}
+
+// one hop through a workflow: the rules tried (in order, up to and including
+// the first match) and the action that was taken
+#[derive(Debug)]
+pub(super) struct Visit<'a> {
+    name: &'a str,
+    checks: Vec<RuleCheck<'a>>,
+    action: &'a Action,
+}
+
+#[derive(Debug)]
+pub(super) struct Trace<'a> {
+    visits: Vec<Visit<'a>>,
+    result: WorkFlowResult,
+}
+
+impl Visit<'_> {
+    fn used_default(&self) -> bool {
+        !self.checks.iter().any(|check| check.matched)
+    }
+}
+
+// same walk as handle_workflow, but keeps a record of every workflow and rule;
+// the workflows have to be validated first, since a cycle would walk forever
+pub(super) fn trace_toy<'a>(
+    workflows: &'a HashMap<WorkflowName, WorkFlow>,
+    entry: &'a str,
+    toy: &Toy,
+) -> Result<Trace<'a>, ValidationError> {
+    let mut visits: Vec<Visit<'a>> = Vec::new();
+    let mut name = entry;
+    loop {
+        let WorkFlow { rules, default } =
+            workflows.get(name).ok_or_else(|| match visits.last() {
+                Some(from) => ValidationError::DanglingReference {
+                    from: from.name.to_string(),
+                    to: name.to_string(),
+                },
+                None => ValidationError::MissingEntry(name.to_string()),
+            })?;
+        let mut checks = Vec::new();
+        let mut action = default;
+        for (rule, rule_action) in rules {
+            let matched = match_rule(rule, toy);
+            checks.push(RuleCheck {
+                rule,
+                value: get(toy, &rule.category),
+                matched,
+            });
+            if matched {
+                action = rule_action;
+                break;
+            }
+        }
+        visits.push(Visit {
+            name,
+            checks,
+            action,
+        });
+        match action {
+            Action::Accept => {
+                return Ok(Trace {
+                    visits,
+                    result: WorkFlowResult::Accepted,
+                })
+            }
+            Action::Reject => {
+                return Ok(Trace {
+                    visits,
+                    result: WorkFlowResult::Rejected,
+                })
+            }
+            Action::Switch(next) => name = next,
+        }
+    }
+}
+
+impl<'a> Trace<'a> {
+    pub(super) fn result(&self) -> WorkFlowResult {
+        self.result
+    }
+
+    // the workflow that accepted or rejected the part
+    pub(super) fn final_workflow(&self) -> &'a str {
+        self.visits.last().unwrap().name
+    }
+
+    pub(super) fn to_json(&self, toy: &Toy) -> Value {
+        let visits: Vec<Value> = self
+            .visits
+            .iter()
+            .map(|visit| {
+                let checks: Vec<Value> = visit
+                    .checks
+                    .iter()
+                    .map(|check| {
+                        json!({
+                            "rule": check.rule.to_string(),
+                            "value": check.value,
+                            "matched": check.matched,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "workflow": visit.name,
+                    "rules": checks,
+                    "default": visit.used_default(),
+                    "action": visit.action.to_string(),
+                })
+            })
+            .collect();
+        json!({
+            "part": { "x": toy.x, "m": toy.m, "a": toy.a, "s": toy.s },
+            "workflows": visits,
+            "result": self.result.to_string(),
+        })
+    }
+}
+
+// in:
+//   1. s<1351 (s=2876) no
+//   default -> qqz
+// qqz:
+//   1. s>2770 (s=2876) yes -> qs
+// ...
+// result: Accepted
+impl fmt::Display for Trace<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for visit in &self.visits {
+            writeln!(f, "{}:", visit.name)?;
+            for (i, check) in visit.checks.iter().enumerate() {
+                let RuleCheck {
+                    rule,
+                    value,
+                    matched,
+                } = check;
+                write!(f, "  {}. {} ({}={})", i + 1, rule, rule.category, value)?;
+                if *matched {
+                    writeln!(f, " yes -> {}", visit.action)?;
+                } else {
+                    writeln!(f, " no")?;
+                }
+            }
+            if visit.used_default() {
+                writeln!(f, "  default -> {}", visit.action)?;
+            }
+        }
+        write!(f, "result: {}", self.result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::trace_toy;
+    use crate::day19::{parse_toy, tests::example, validate::ValidationError, WorkFlowResult};
+
+    #[test]
+    fn renders_an_accepted_part() {
+        let workflows = example();
+        let toy = parse_toy("{x=787,m=2655,a=1222,s=2876}").unwrap();
+        let trace = trace_toy(&workflows, "in", &toy).unwrap();
+        assert_eq!(trace.result(), WorkFlowResult::Accepted);
+        assert_eq!(trace.final_workflow(), "lnx");
+        assert_eq!(
+            trace.to_string(),
+            "in:
+  1. s<1351 (s=2876) no
+  default -> qqz
+qqz:
+  1. s>2770 (s=2876) yes -> qs
+qs:
+  1. s>3448 (s=2876) no
+  default -> lnx
+lnx:
+  1. m>1548 (m=2655) yes -> A
+result: Accepted"
+        );
+    }
+
+    #[test]
+    fn renders_json() {
+        let workflows = example();
+        let toy = parse_toy("{x=787,m=2655,a=1222,s=2876}").unwrap();
+        let trace = trace_toy(&workflows, "in", &toy).unwrap();
+        assert_eq!(
+            trace.to_json(&toy),
+            json!({
+                "part": { "x": 787, "m": 2655, "a": 1222, "s": 2876 },
+                "workflows": [
+                    {
+                        "workflow": "in",
+                        "rules": [{ "rule": "s<1351", "value": 2876, "matched": false }],
+                        "default": true,
+                        "action": "qqz",
+                    },
+                    {
+                        "workflow": "qqz",
+                        "rules": [{ "rule": "s>2770", "value": 2876, "matched": true }],
+                        "default": false,
+                        "action": "qs",
+                    },
+                    {
+                        "workflow": "qs",
+                        "rules": [{ "rule": "s>3448", "value": 2876, "matched": false }],
+                        "default": true,
+                        "action": "lnx",
+                    },
+                    {
+                        "workflow": "lnx",
+                        "rules": [{ "rule": "m>1548", "value": 2655, "matched": true }],
+                        "default": false,
+                        "action": "A",
+                    },
+                ],
+                "result": "Accepted",
+            })
+        );
+    }
+
+    #[test]
+    fn renders_a_rejected_part() {
+        let workflows = example();
+        let toy = parse_toy("{x=1679,m=44,a=2067,s=496}").unwrap();
+        let trace = trace_toy(&workflows, "in", &toy).unwrap();
+        assert_eq!(trace.result(), WorkFlowResult::Rejected);
+        assert_eq!(trace.final_workflow(), "gd");
+        assert_eq!(
+            trace.to_string(),
+            "in:
+  1. s<1351 (s=496) yes -> px
+px:
+  1. a<2006 (a=2067) no
+  2. m>2090 (m=44) no
+  default -> rfg
+rfg:
+  1. s<537 (s=496) yes -> gd
+gd:
+  1. a>3333 (a=2067) no
+  default -> R
+result: Rejected"
+        );
+        let json = trace.to_json(&toy);
+        assert_eq!(json["result"], "Rejected");
+        assert_eq!(json["workflows"][1]["default"], true);
+        assert_eq!(json["workflows"][3]["action"], "R");
+    }
+
+    #[test]
+    fn missing_workflows_are_errors() {
+        let toy = parse_toy("{x=1,m=2,a=3,s=4}").unwrap();
+        let mut workflows = example();
+        assert_eq!(
+            trace_toy(&workflows, "nope", &toy).unwrap_err(),
+            ValidationError::MissingEntry("nope".to_string())
+        );
+        workflows.remove("px");
+        assert_eq!(
+            trace_toy(&workflows, "in", &toy).unwrap_err(),
+            ValidationError::DanglingReference {
+                from: "in".to_string(),
+                to: "px".to_string()
+            }
+        );
+    }
+}
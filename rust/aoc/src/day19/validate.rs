@@ -0,0 +1,101 @@
+use std::{collections::HashMap, fmt};
+
+use super::{Action, WorkFlow, WorkflowName};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum ValidationError {
+    MissingEntry(WorkflowName),
+    DanglingReference {
+        from: WorkflowName,
+        to: WorkflowName,
+    },
+    // the workflows along the cycle, starting and ending with the same one
+    Cycle(Vec<WorkflowName>),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::MissingEntry(entry) => write!(f, "missing {} workflow", entry),
+            ValidationError::DanglingReference { from, to } => {
+                write!(f, "{} refers to unknown workflow {}", from, to)
+            }
+            ValidationError::Cycle(names) => write!(f, "workflow cycle {}", names.join(" -> ")),
+        }
+    }
+}
+
+fn targets(workflow: &WorkFlow) -> impl Iterator<Item = &WorkflowName> {
+    workflow
+        .rules
+        .iter()
+        .map(|(_, action)| action)
+        .chain(std::iter::once(&workflow.default))
+        .filter_map(|action| match action {
+            Action::Switch(name) => Some(name),
+            _ => None,
+        })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+fn find_cycle<'a>(
+    workflows: &'a HashMap<WorkflowName, WorkFlow>,
+    name: &'a WorkflowName,
+    visits: &mut HashMap<&'a WorkflowName, Visit>,
+    path: &mut Vec<&'a WorkflowName>,
+) -> Option<Vec<WorkflowName>> {
+    match visits.get(name) {
+        Some(Visit::Done) => return None,
+        Some(Visit::InProgress) => {
+            let start = path.iter().position(|on_path| *on_path == name).unwrap();
+            let mut cycle: Vec<WorkflowName> =
+                path[start..].iter().map(|n| n.to_string()).collect();
+            cycle.push(name.clone());
+            return Some(cycle);
+        }
+        None => {}
+    }
+    visits.insert(name, Visit::InProgress);
+    path.push(name);
+    for next in targets(&workflows[name]) {
+        if let Some(cycle) = find_cycle(workflows, next, visits, path) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    visits.insert(name, Visit::Done);
+    None
+}
+
+// the entry exists, every referenced workflow exists, and no part can loop
+// forever; handle_workflow and solve assume all three
+pub(super) fn validate(
+    workflows: &HashMap<WorkflowName, WorkFlow>,
+    entry: &str,
+) -> Result<(), ValidationError> {
+    if !workflows.contains_key(entry) {
+        return Err(ValidationError::MissingEntry(entry.to_string()));
+    }
+    let mut names: Vec<&WorkflowName> = workflows.keys().collect();
+    names.sort();
+    for name in &names {
+        if let Some(to) = targets(&workflows[*name]).find(|to| !workflows.contains_key(*to)) {
+            return Err(ValidationError::DanglingReference {
+                from: name.to_string(),
+                to: to.clone(),
+            });
+        }
+    }
+    let mut visits = HashMap::new();
+    for name in names {
+        if let Some(cycle) = find_cycle(workflows, name, &mut visits, &mut Vec::new()) {
+            return Err(ValidationError::Cycle(cycle));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+};
+
+// a puzzle input read a line at a time, so only the current line is ever held
+// in memory; `-` reads stdin
+pub(crate) fn open(path: &str) -> Result<Box<dyn BufRead>, String> {
+    if path == "-" {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    let file = File::open(path).map_err(|err| format!("unable to read {}: {}", path, err))?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+// the lines numbered from 1, with read errors (invalid utf-8 included) turned
+// into messages naming the line
+pub(crate) fn lines(reader: impl BufRead) -> impl Iterator<Item = Result<(usize, String), String>> {
+    reader.lines().enumerate().map(|(i, line)| {
+        line.map(|line| (i + 1, line))
+            .map_err(|err| format!("line {}: {}", i + 1, err))
+    })
+}
+
+// how the file is named in error messages
+pub(crate) fn name(path: &str) -> &str {
+    if path == "-" {
+        "stdin"
+    } else {
+        path
+    }
+}
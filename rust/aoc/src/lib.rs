@@ -0,0 +1,7 @@
+pub mod day1;
+// an earlier puzzle kept as scratch work; nothing on the command line runs it
+#[allow(dead_code)]
+mod day10;
+pub mod day12;
+pub mod day19;
+mod input;
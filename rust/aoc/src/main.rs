@@ -1,64 +1,217 @@
-use std::fs;
-mod day10;
-mod day12;
-mod day19;
+use aoc::{day1, day12, day19};
+use std::{env, process};
+
+const USAGE: &str = "usage:
+  aoc
+  aoc solve-day19 <rules-file> [--entry=NAME] [--region=x=1..=100,m<2000]
+  aoc explain-part <rules-file> <part> [--json]
+  aoc bench-day19 <rules-file> [parts]
+  aoc bench-solve-day19 [--seed=N] [--depth=N] [--tail=N]
+  aoc optimize-day19 <rules-file>
+  aoc workflow-diff <old-rules-file> <new-rules-file>
+  aoc batch-day19 <rules-file> <parts-file> [--format=csv|jsonl] [--output=csv|jsonl]
+  aoc query-day19 <rules-file> [--witnesses]
+  aoc reach-day19 <rules-file> [workflow|workflow:rule|workflow:default]
+  aoc repl <rules-file>
+  aoc day1 <input-file|-> [--part=1|2] [--dict=en|de|fr|es|it|<dictionary-file>]
+  aoc day12 <input-file|->
+  aoc arrangements-day12 <row> [--limit=N | --sample=K [--seed=N]]
+  aoc forced-day12 <row>
+  aoc nonogram <puzzle.non>
+  aoc day19 <input-file|->
+  aoc generate-day19 [--seed=N] [--depth=N] [--fan-out=N] [--parts=N] [--fault=dangling|cycle|malformed]
+a flag's value can also be given as the next argument, e.g. --limit 5";
+
+// the flags that take a value
+const VALUE_FLAGS: &[&str] = &[
+    "--entry",
+    "--region",
+    "--seed",
+    "--depth",
+    "--tail",
+    "--format",
+    "--output",
+    "--part",
+    "--dict",
+    "--limit",
+    "--sample",
+    "--fan-out",
+    "--parts",
+    "--fault",
+];
 
 fn main() {
-    // let val = fs::read_to_string("/Users/deverkemmenash/Desktop/2023/AoC/rust/aoc/inputs/day_1.txt")
-    //     .expect("Unable to read file")
-    //     .lines()
-    //     .map(|line| handle_line(translate(line.to_string())))
-    //     .fold(0, |acc, x| acc + x);
-    // println!("result is: {}", val);
-    day19::main();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
 }
 
-struct Pattern {
-    pattern: String,
-    replacement: String,
+fn run(args: &[String]) -> Result<(), String> {
+    let args = join_flag_values(args)?;
+    let flags: Vec<&str> = args
+        .iter()
+        .filter(|arg| arg.starts_with("--"))
+        .map(|arg| arg.as_str())
+        .collect();
+    let positional: Vec<&str> = args
+        .iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .map(|arg| arg.as_str())
+        .collect();
+    match positional[..] {
+        [] => {
+            day19::main();
+            Ok(())
+        }
+        ["solve-day19", rules] => {
+            let entry = flag(&flags, "--entry").unwrap_or("in");
+            let region = flag(&flags, "--region").unwrap_or("");
+            println!("{}", day19::solve_rules(rules, entry, region)?);
+            Ok(())
+        }
+        ["explain-part", rules, part] => {
+            let json = flags.contains(&"--json");
+            println!("{}", day19::explain_part(rules, part, json)?);
+            Ok(())
+        }
+        ["bench-day19", rules] => {
+            println!("{}", day19::bench_compiled(rules, 1_000_000)?);
+            Ok(())
+        }
+        ["bench-day19", rules, count] => {
+            let count = count
+                .parse()
+                .map_err(|_| format!("invalid part count {}", count))?;
+            println!("{}", day19::bench_compiled(rules, count)?);
+            Ok(())
+        }
+        ["bench-solve-day19"] => {
+            let seed = flag_value(&flags, "--seed", 0)?;
+            let depth = flag_value(&flags, "--depth", 16)?;
+            let tail = flag_value(&flags, "--tail", 200)?;
+            println!("{}", day19::bench_solve(seed, depth as usize, tail as usize)?);
+            Ok(())
+        }
+        ["optimize-day19", rules] => {
+            println!("{}", day19::optimize_rules(rules)?);
+            Ok(())
+        }
+        ["workflow-diff", old, new] => {
+            println!("{}", day19::workflow_diff(old, new)?);
+            Ok(())
+        }
+        ["batch-day19", rules, parts] => {
+            let format = flag(&flags, "--format");
+            let output = flag(&flags, "--output");
+            print!("{}", day19::batch_evaluate(rules, parts, format, output)?);
+            Ok(())
+        }
+        ["reach-day19", rules] => {
+            println!("{}", day19::reach_query(rules, None)?);
+            Ok(())
+        }
+        ["reach-day19", rules, target] => {
+            println!("{}", day19::reach_query(rules, Some(target))?);
+            Ok(())
+        }
+        ["repl", rules] => day19::repl(rules),
+        ["query-day19", rules] => {
+            let witnesses = flags.contains(&"--witnesses");
+            println!("{}", day19::query_accepted(rules, witnesses)?);
+            Ok(())
+        }
+        ["day1", input] => {
+            let mode = flag(&flags, "--part").unwrap_or("2").parse()?;
+            println!("{}", day1::solve(input, mode, flag(&flags, "--dict"))?);
+            Ok(())
+        }
+        ["day12", input] => {
+            println!("{}", day12::solve(input)?);
+            Ok(())
+        }
+        ["arrangements-day12", row] => {
+            let limit = flag(&flags, "--limit")
+                .map(|limit| {
+                    limit
+                        .parse()
+                        .map_err(|_| format!("invalid value for --limit: {}", limit))
+                })
+                .transpose()?;
+            if let Some(count) = flag(&flags, "--sample") {
+                let count = count
+                    .parse()
+                    .map_err(|_| format!("invalid value for --sample: {}", count))?;
+                let seed = flag_value(&flags, "--seed", 0)?;
+                println!("{}", day12::sample_arrangements(row, count, seed)?);
+                return Ok(());
+            }
+            println!("{}", day12::arrangements(row, limit)?);
+            Ok(())
+        }
+        ["forced-day12", row] => {
+            println!("{}", day12::forced_cells(row)?);
+            Ok(())
+        }
+        ["nonogram", puzzle] => {
+            println!("{}", day12::solve_nonogram(puzzle)?);
+            Ok(())
+        }
+        ["day19", input] => {
+            println!("{}", day19::solve_input(input)?);
+            Ok(())
+        }
+        ["generate-day19"] => {
+            let seed = flag_value(&flags, "--seed", 0)?;
+            let depth = flag_value(&flags, "--depth", 4)?;
+            let fan_out = flag_value(&flags, "--fan-out", 4)?;
+            let parts = flag_value(&flags, "--parts", 200)?;
+            let fault = flag(&flags, "--fault");
+            print!(
+                "{}",
+                day19::generate_input(
+                    seed,
+                    depth as usize,
+                    fan_out as usize,
+                    parts as usize,
+                    fault
+                )?
+            );
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
 }
 
-fn translate_helper(s: String, index: usize, patterns: [Pattern; 9]) -> String {
-    if s == "" {
-        return s;
-    } else if index == patterns.len() {
-        return s[0..1].to_string() + &translate(s[1..].to_string());
-    } else {
-        let pattern = &patterns[index];
-        if s.starts_with(&pattern.pattern) {
-            return "".to_string() + &pattern.replacement + &translate(s[pattern.pattern.len()..].to_string());
+// `--limit 5` becomes `--limit=5`, so the value isn't taken for a positional
+// argument
+fn join_flag_values(args: &[String]) -> Result<Vec<String>, String> {
+    let mut joined = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            let value = args.next().ok_or(format!("{} needs a value", arg))?;
+            joined.push(format!("{}={}", arg, value));
         } else {
-            return translate_helper(s, index + 1, patterns);
+            joined.push(arg.clone());
         }
     }
+    Ok(joined)
 }
 
-fn translate(s: String) -> String {
-    let patterns = [
-        Pattern { pattern: "one".to_string(), replacement: "1".to_string() },
-        Pattern { pattern: "two".to_string(), replacement: "2".to_string() },
-        Pattern { pattern: "three".to_string(), replacement: "3".to_string() },
-        Pattern { pattern: "four".to_string(), replacement: "4".to_string() },
-        Pattern { pattern: "five".to_string(), replacement: "5".to_string() },
-        Pattern { pattern: "six".to_string(), replacement: "6".to_string() },
-        Pattern { pattern: "seven".to_string(), replacement: "7".to_string() },
-        Pattern { pattern: "eight".to_string(), replacement: "8".to_string() },
-        Pattern { pattern: "nine".to_string(), replacement: "9".to_string() },
-    ];
-    return translate_helper(s, 0, patterns)
+// the value of a `--name=value` flag
+fn flag<'a>(flags: &[&'a str], name: &str) -> Option<&'a str> {
+    let prefix = format!("{}=", name);
+    flags.iter().find_map(|flag| flag.strip_prefix(&prefix))
 }
 
-fn handle_line(s: String) -> u32 {
-    println!("translated: {}", s);
-    let nums = s.chars()
-                        .filter(|c| c.is_numeric())
-                        // .map(|c| c.to_string())
-                        .collect::<Vec<char>>();
-                        // .map(|c| c.to_digit(10).expect("A non-numeric value made it past the filter"))
-                        // .collect::<Vec<u32>>();
-    let first = nums.first().expect("no first value");
-    let last = nums.last().expect("no last value");
-    let val = (first.to_string() + &last.to_string()).parse().expect("Not a number");
-    println!("first: {}, last: {}, val: {}", first, last, val);
-    return val
-}
\ No newline at end of file
+// a numeric `--name=value` flag, falling back to `default` when absent
+fn flag_value(flags: &[&str], name: &str, default: u64) -> Result<u64, String> {
+    match flag(flags, name) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("invalid value for {}: {}", name, value)),
+        None => Ok(default),
+    }
+}